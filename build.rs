@@ -0,0 +1,45 @@
+// Copyright (c) 2024 Typox Project Contributors
+// Licensed under the MIT License - see LICENSE file for details
+
+// Records the build environment reported by `typox info` and the plugin's
+// get_version. The plugin's build script includes this file.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let lockfile = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Cargo writes the lockfile before build scripts run
+    let oxigraph_version = std::fs::read_to_string(&lockfile)
+        .ok()
+        .and_then(|lock| locked_version(&lock, "oxigraph"))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=TYPOX_OXIGRAPH_VERSION={}", oxigraph_version);
+    println!("cargo:rustc-env=TYPOX_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=TYPOX_TARGET={}", env::var("TARGET").unwrap());
+    println!("cargo:rustc-env=TYPOX_PROFILE={}", env::var("PROFILE").unwrap());
+}
+
+// Version of a package in the Cargo.lock format: `name = "..."` followed by `version = "..."`
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    lines.find(|line| line.trim() == name_line)?;
+    let version = lines.next()?.trim().strip_prefix("version = \"")?;
+    Some(version.trim_end_matches('"').to_string())
+}
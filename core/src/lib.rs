@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) 2024 Typox Project Contributors
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Conversion rules shared by the `typox` CLI and the WASM plugin.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod namespaces;
+
+use alloc::string::ToString;
+use core::num::IntErrorKind;
+use serde_json::{Number, Value};
+
+/// Version of the JSON contract shared by `typox query` output and the plugin
+/// functions, independent of the crate versions. The major version changes when
+/// existing output changes shape, the minor version when functions or fields are
+/// added. See the API compatibility table in the README.
+pub const API_VERSION: &str = "1.0.0";
+
+// XSD datatype IRIs
+pub const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
+pub const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+pub const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+pub const XSD_INT: &str = "http://www.w3.org/2001/XMLSchema#int";
+pub const XSD_LONG: &str = "http://www.w3.org/2001/XMLSchema#long";
+pub const XSD_SHORT: &str = "http://www.w3.org/2001/XMLSchema#short";
+pub const XSD_BYTE: &str = "http://www.w3.org/2001/XMLSchema#byte";
+pub const XSD_NON_NEGATIVE_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#nonNegativeInteger";
+pub const XSD_POSITIVE_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#positiveInteger";
+pub const XSD_UNSIGNED_INT: &str = "http://www.w3.org/2001/XMLSchema#unsignedInt";
+pub const XSD_UNSIGNED_LONG: &str = "http://www.w3.org/2001/XMLSchema#unsignedLong";
+pub const XSD_UNSIGNED_SHORT: &str = "http://www.w3.org/2001/XMLSchema#unsignedShort";
+pub const XSD_UNSIGNED_BYTE: &str = "http://www.w3.org/2001/XMLSchema#unsignedByte";
+pub const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+pub const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+pub const XSD_FLOAT: &str = "http://www.w3.org/2001/XMLSchema#float";
+pub const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+
+/// An RDF literal as seen by the JSON conversion: its lexical form, datatype
+/// IRI and optional language tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedLiteral<'a> {
+    pub value: &'a str,
+    pub datatype: &'a str,
+    pub language: Option<&'a str>,
+}
+
+impl<'a> TypedLiteral<'a> {
+    pub fn new(value: &'a str, datatype: &'a str, language: Option<&'a str>) -> Self {
+        TypedLiteral {
+            value,
+            datatype,
+            language,
+        }
+    }
+
+    /// Converts the literal to JSON: integer and floating point datatypes become
+    /// numbers and `xsd:boolean` becomes a boolean. Every other literal, plain and
+    /// language-tagged strings included, is returned as its lexical form, so
+    /// values such as `"00123"` keep their leading zeros. Integers outside the
+    /// `i64` range, such as large `xsd:unsignedLong` values, are kept as strings,
+    /// see [`Self::overflows_i64`].
+    pub fn to_json_value(&self) -> Value {
+        if self.language.is_some() {
+            return Value::String(self.value.to_string());
+        }
+
+        match self.datatype {
+            datatype if is_integer_datatype(datatype) => {
+                // Typst integers are 64-bit signed, and going through f64 would
+                // silently change the value, so overflowing values stay strings
+                if let Ok(num) = self.value.parse::<i64>() {
+                    return Value::Number(Number::from(num));
+                }
+            }
+            XSD_DECIMAL | XSD_DOUBLE | XSD_FLOAT => {
+                if let Some(num) = parse_float(self.value) {
+                    return num;
+                }
+            }
+            // "1" and "0" are valid lexical forms of true and false
+            XSD_BOOLEAN => match self.value {
+                "true" | "1" => return Value::Bool(true),
+                "false" | "0" => return Value::Bool(false),
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Value::String(self.value.to_string())
+    }
+
+    /// Whether this is an integer literal too large (or too small) for an `i64`,
+    /// which [`Self::to_json_value`] returns as a string rather than a number.
+    pub fn overflows_i64(&self) -> bool {
+        is_integer_datatype(self.datatype)
+            && matches!(self.value.parse::<i64>(), Err(e) if is_overflow(e.kind()))
+    }
+}
+
+fn is_integer_datatype(datatype: &str) -> bool {
+    matches!(
+        datatype,
+        XSD_INTEGER
+            | XSD_INT
+            | XSD_LONG
+            | XSD_SHORT
+            | XSD_BYTE
+            | XSD_NON_NEGATIVE_INTEGER
+            | XSD_POSITIVE_INTEGER
+            | XSD_UNSIGNED_INT
+            | XSD_UNSIGNED_LONG
+            | XSD_UNSIGNED_SHORT
+            | XSD_UNSIGNED_BYTE
+    )
+}
+
+fn is_overflow(kind: &IntErrorKind) -> bool {
+    matches!(kind, IntErrorKind::PosOverflow | IntErrorKind::NegOverflow)
+}
+
+// NaN and infinities have no JSON representation
+fn parse_float(value: &str) -> Option<Value> {
+    let num = value.parse::<f64>().ok()?;
+    Number::from_f64(num).map(Value::Number)
+}
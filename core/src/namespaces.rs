@@ -0,0 +1,41 @@
+/*
+ * Copyright (c) 2024 Typox Project Contributors
+ * Licensed under the MIT License - see LICENSE file for details
+ */
+
+//! IRIs of the vocabularies typox knows about, so that namespace strings are
+//! written once.
+
+// RDF
+pub const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+
+// RDF Schema
+pub const RDFS_NS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+
+// OWL
+pub const OWL_NS: &str = "http://www.w3.org/2002/07/owl#";
+
+// Other vocabularies with a built-in prefix
+pub const FOAF_NS: &str = "http://xmlns.com/foaf/0.1/";
+pub const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+pub const DCTERMS_NS: &str = "http://purl.org/dc/terms/";
+pub const SKOS_NS: &str = "http://www.w3.org/2004/02/skos/core#";
+
+// VoID, used by `typox load --generate-void`
+pub const VOID_NS: &str = "http://rdfs.org/ns/void#";
+pub const VOID_DATASET: &str = "http://rdfs.org/ns/void#dataset";
+
+/// Prefixes that query results are shortened with even when the query does not
+/// declare them.
+pub const COMMON_PREFIXES: &[(&str, &str)] = &[
+    ("rdf", RDF_NS),
+    ("rdfs", RDFS_NS),
+    ("owl", OWL_NS),
+    ("xsd", crate::XSD_NS),
+    ("foaf", FOAF_NS),
+    ("dc", DC_NS),
+    ("dcterms", DCTERMS_NS),
+    ("skos", SKOS_NS),
+];
@@ -0,0 +1,5 @@
+// Copyright (c) 2024 Typox Project Contributors
+// Licensed under the MIT License - see LICENSE file for details
+
+// Same build information as the CLI, read from the plugin's own Cargo.lock
+include!("../build.rs");
@@ -25,15 +25,25 @@
 
 use wasm_minimal_protocol::{initiate_protocol, wasm_func};
 use alloc::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, VecDeque},
     format,
     string::{String, ToString},
     vec::Vec,
 };
 use oxigraph::store::Store;
-use oxigraph::io::RdfFormat;
-use oxigraph::sparql::QueryResults;
+use oxigraph::io::{JsonLdProfileSet, RdfFormat, RdfParser};
+use oxigraph::model::{BlankNode, GraphName, GraphNameRef, Literal, NamedNode, NamedOrBlankNode, Quad, Term, Triple};
+use oxigraph::sparql::{
+    DefaultServiceHandler, QueryEvaluationError, QueryResults, QuerySolutionIter, SparqlEvaluator,
+};
+use oxiri::Iri;
+use spargebra::algebra::GraphPattern;
+use oxttl::turtle::{LowLevelTurtleParser, TurtleParser};
+use oxjsonld::JsonLdSerializer;
+use base64::prelude::{Engine, BASE64_STANDARD};
 use serde_json::{json, Value};
+use typox_core::namespaces::RDF_TYPE;
+use typox_core::{TypedLiteral, API_VERSION};
 
 extern crate alloc;
 
@@ -56,13 +66,99 @@ pub fn __getrandom_custom(buf: &mut [u8]) -> Result<(), getrandom::Error> {
 
 initiate_protocol!();
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 // Global state management for WASM plugin
 // Using BTreeMap instead of HashMap for no_std compatibility
 static mut STORES: Option<BTreeMap<String, Store>> = None;
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+// Prefixes registered with add_prefix, keyed by store name then prefix
+static mut PREFIXES: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+// Turtle loads fed chunk by chunk, keyed by the session ID returned by load_turtle_begin
+static mut LOAD_SESSIONS: BTreeMap<String, LoadSession> = BTreeMap::new();
+static NEXT_LOAD_SESSION: AtomicU64 = AtomicU64::new(1);
+
+struct LoadSession {
+    store_name: String,
+    parser: LowLevelTurtleParser,
+    loaded: usize,
+    // Fresh blank nodes for the labels of the document, as load_turtle does, so
+    // that `_:b` never matches a `_:b` of another load
+    blank_nodes: BTreeMap<String, BlankNode>,
+}
+
+impl LoadSession {
+    fn rename_triple(&mut self, triple: Triple) -> Triple {
+        let subject = match triple.subject {
+            NamedOrBlankNode::BlankNode(node) => self.rename_blank_node(node).into(),
+            subject => subject,
+        };
+        Triple::new(subject, triple.predicate, self.rename_term(triple.object))
+    }
+
+    fn rename_term(&mut self, term: Term) -> Term {
+        match term {
+            Term::BlankNode(node) => self.rename_blank_node(node).into(),
+            Term::Triple(triple) => self.rename_triple(*triple).into(),
+            term => term,
+        }
+    }
+
+    fn rename_blank_node(&mut self, node: BlankNode) -> BlankNode {
+        self.blank_nodes.entry(node.into_string()).or_default().clone()
+    }
+}
+
+// SELECT results read page by page, keyed by the cursor ID returned by query_paged_open
+static mut PAGINATION_STATES: BTreeMap<String, PaginationState> = BTreeMap::new();
+static NEXT_CURSOR: AtomicU64 = AtomicU64::new(1);
+
+// The rows are computed when the cursor is opened: solution iterators borrow the
+// store, so they cannot outlive the plugin call that created them
+struct PaginationState {
+    rows: Vec<Value>,
+    position: usize,
+}
+
+// IRI scheme of SERVICE calls that target a plugin store, as in SERVICE <store://name>
+const LOCAL_SERVICE_SCHEME: &str = "store://";
+
+// Number of rows query and the other JSON query functions return before failing,
+// so that a runaway SELECT cannot exhaust the plugin's memory
+const MAX_QUERY_ROWS: usize = 100_000;
+
+// Input size in bytes from which load_ntriples_bulk uses the bulk loader
+const BULK_LOAD_THRESHOLD: usize = 1024 * 1024;
+
+// Number of solutions inspected by query_results_schema
+const SCHEMA_SAMPLE_SIZE: usize = 100;
+
+// Number of quads buffered before each insertion when merging stores, keeping
+// memory usage bounded while avoiding one insertion per quad
+const MERGE_BATCH_SIZE: usize = 10_000;
+
+// Distinct subject/predicate/object counts across the default and named graphs
+const STORE_INFO_QUERY: &str = "SELECT (COUNT(DISTINCT ?s) AS ?subjects) (COUNT(DISTINCT ?p) AS ?predicates) (COUNT(DISTINCT ?o) AS ?objects) \
+     WHERE { { ?s ?p ?o } UNION { GRAPH ?g { ?s ?p ?o } } }";
+
+// Queries of store_statistics_query, all over the default and named graphs. In the
+// counts query ?iri, ?literal and ?bnode are bound through IF(..., ?none), where
+// the unbound ?none is an error that leaves them unbound for terms of other kinds.
+const STORE_STATISTICS_QUERY: &str = "SELECT (COUNT(*) AS ?triples) (COUNT(DISTINCT ?s) AS ?subjects) \
+     (COUNT(DISTINCT ?p) AS ?predicates) (COUNT(DISTINCT ?o) AS ?objects) (COUNT(DISTINCT ?iri) AS ?iri_objects) \
+     (COUNT(DISTINCT ?literal) AS ?literal_objects) (COUNT(DISTINCT ?bnode) AS ?blank_node_subjects) \
+     WHERE { { ?s ?p ?o } UNION { GRAPH ?g { ?s ?p ?o } } \
+     BIND(IF(isIRI(?o), ?o, ?none) AS ?iri) BIND(IF(isLiteral(?o), ?o, ?none) AS ?literal) \
+     BIND(IF(isBlank(?s), ?s, ?none) AS ?bnode) }";
+const TOP_PREDICATES_QUERY: &str = "SELECT ?predicate (COUNT(*) AS ?count) \
+     WHERE { { ?s ?predicate ?o } UNION { GRAPH ?g { ?s ?predicate ?o } } } \
+     GROUP BY ?predicate ORDER BY DESC(?count) ?predicate LIMIT 5";
+const TOP_TYPES_QUERY: &str = "SELECT ?type (COUNT(DISTINCT ?s) AS ?count) \
+     WHERE { { ?s a ?type } UNION { GRAPH ?g { ?s a ?type } } } \
+     GROUP BY ?type ORDER BY DESC(?count) ?type LIMIT 5";
+
 // Initialize stores
 fn ensure_stores() {
     if !INITIALIZED.load(Ordering::Acquire) {
@@ -93,6 +189,52 @@ where
     }
 }
 
+// Get mutable reference to the prefix registry
+fn with_prefixes_mut<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut BTreeMap<String, BTreeMap<String, String>>) -> R,
+{
+    unsafe { f(&mut *core::ptr::addr_of_mut!(PREFIXES)) }
+}
+
+// Get mutable reference to the streaming load sessions
+fn with_load_sessions_mut<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut BTreeMap<String, LoadSession>) -> R,
+{
+    unsafe { f(&mut *core::ptr::addr_of_mut!(LOAD_SESSIONS)) }
+}
+
+// Get mutable reference to the open pagination cursors
+fn with_pagination_states_mut<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut BTreeMap<String, PaginationState>) -> R,
+{
+    unsafe { f(&mut *core::ptr::addr_of_mut!(PAGINATION_STATES)) }
+}
+
+// Store names are case-insensitive and surrounding whitespace is ignored, so
+// "MyStore", "mystore" and " mystore " all refer to the same store. Every function
+// taking a store name passes it through here before touching STORES or PREFIXES;
+// list_stores returns the normalized names, in sorted order since STORES is a BTreeMap.
+fn normalize_store_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+// Parses the store name argument of a plugin function. The error is the message
+// to return from the function as is.
+fn store_name_arg(store_name: &[u8]) -> Result<String, Vec<u8>> {
+    store_name_arg_for(store_name, "store name")
+}
+
+// Like store_name_arg, for functions with several store arguments
+fn store_name_arg_for(store_name: &[u8], what: &str) -> Result<String, Vec<u8>> {
+    match core::str::from_utf8(store_name) {
+        Ok(name) => Ok(normalize_store_name(name)),
+        Err(e) => Err(format!("ERROR: Invalid {}: {}", what, e).into_bytes()),
+    }
+}
+
 // Helper function to get or create a store
 fn get_or_create_store<'a>(stores: &'a mut BTreeMap<String, Store>, store_name: &str) -> Result<&'a mut Store, String> {
     if !stores.contains_key(store_name) {
@@ -102,13 +244,186 @@ fn get_or_create_store<'a>(stores: &'a mut BTreeMap<String, Store>, store_name:
     stores.get_mut(store_name).ok_or_else(|| "Failed to get store".to_string())
 }
 
+// Parse and evaluate a SPARQL query against a store. SERVICE <store://name> calls
+// are evaluated against the plugin store called name.
+fn run_query(store: &Store, sparql: &str, stores: &BTreeMap<String, Store>) -> Result<QueryResults<'static>, String> {
+    SparqlEvaluator::new()
+        .with_default_service_handler(LocalStoreServices::new(stores))
+        .parse_query(sparql)
+        .map_err(|e| format!("SPARQL query parsing failed: {}", e))?
+        .on_store(store)
+        .execute()
+        .map_err(|e| format!("SPARQL query execution failed: {}", e))
+}
+
+// Evaluates SERVICE <store://name> { ... } against the plugin store called name.
+// The plugin has no HTTP client, so any other service IRI is unsupported. Stores
+// are captured when the query starts: updates made afterwards are not visible.
+struct LocalStoreServices {
+    stores: BTreeMap<String, Store>,
+}
+
+impl LocalStoreServices {
+    fn new(stores: &BTreeMap<String, Store>) -> Self {
+        Self { stores: stores.clone() }
+    }
+}
+
+impl DefaultServiceHandler for LocalStoreServices {
+    type Error = QueryEvaluationError;
+
+    fn handle(
+        &self,
+        service_name: &NamedNode,
+        pattern: &GraphPattern,
+        base_iri: Option<&Iri<String>>,
+    ) -> Result<QuerySolutionIter<'static>, Self::Error> {
+        let Some(name) = service_name.as_str().strip_prefix(LOCAL_SERVICE_SCHEME) else {
+            return Err(QueryEvaluationError::UnsupportedService(service_name.clone()));
+        };
+        let name = normalize_store_name(name);
+        let store = self
+            .stores
+            .get(&name)
+            .ok_or_else(|| QueryEvaluationError::Service(format!("Store '{}' not found", name).into()))?;
+
+        let query = spargebra::Query::Select {
+            dataset: None,
+            pattern: pattern.clone(),
+            base_iri: base_iri.cloned(),
+        };
+        match SparqlEvaluator::new().for_query(query).on_store(store).execute()? {
+            QueryResults::Solutions(solutions) => Ok(solutions),
+            _ => Err(QueryEvaluationError::Service("SERVICE did not return solutions".into())),
+        }
+    }
+}
+
+// Insert every quad of the source store into the target in batches of MERGE_BATCH_SIZE
+fn merge_quads(source: &Store, target: &Store) -> Result<usize, String> {
+    let mut merged = 0;
+    let mut batch = Vec::with_capacity(MERGE_BATCH_SIZE);
+
+    for quad in source.iter() {
+        batch.push(quad.map_err(|e| format!("Error reading quad: {}", e))?);
+        if batch.len() == MERGE_BATCH_SIZE {
+            merged += batch.len();
+            target
+                .extend(batch.drain(..))
+                .map_err(|e| format!("Failed to insert quads: {}", e))?;
+        }
+    }
+
+    merged += batch.len();
+    target
+        .extend(batch)
+        .map_err(|e| format!("Failed to insert quads: {}", e))?;
+
+    Ok(merged)
+}
+
+// IRIs are returned as-is and blank nodes in their `_:id` form
+fn node_to_string(node: &NamedOrBlankNode) -> String {
+    match node {
+        NamedOrBlankNode::NamedNode(n) => n.as_str().to_string(),
+        NamedOrBlankNode::BlankNode(b) => format!("_:{}", b.as_str()),
+    }
+}
+
+// Convert SELECT solutions to a JSON array of rows and ASK results to {"boolean": b}
+fn results_to_json(results: QueryResults, prefixes: &BTreeMap<String, String>) -> Result<String, String> {
+    match results {
+        QueryResults::Solutions(solutions) => {
+            let (result_rows, truncated) = solutions_to_rows(solutions, prefixes, MAX_QUERY_ROWS)?;
+            if truncated {
+                return Err(format!(
+                    "Query returned more than {} rows, add a LIMIT or use query_limited",
+                    MAX_QUERY_ROWS
+                ));
+            }
+
+            serde_json::to_string(&result_rows)
+                .map_err(|e| format!("JSON serialization error: {}", e))
+        }
+        QueryResults::Boolean(b) => {
+            Ok(json!({"boolean": b}).to_string())
+        }
+        QueryResults::Graph(_) => {
+            Err("CONSTRUCT queries should use query_construct function".to_string())
+        }
+    }
+}
+
+// Convert up to max_rows solutions to JSON objects. The second value tells whether
+// further solutions were left unread.
+fn solutions_to_rows(
+    solutions: QuerySolutionIter,
+    prefixes: &BTreeMap<String, String>,
+    max_rows: usize,
+) -> Result<(Vec<Value>, bool), String> {
+    let mut result_rows = Vec::new();
+
+    for solution in solutions {
+        if result_rows.len() == max_rows {
+            return Ok((result_rows, true));
+        }
+
+        let solution = solution.map_err(|e| format!("Error reading solution: {}", e))?;
+        let mut row = serde_json::Map::new();
+
+        for (var, term) in solution.iter() {
+            let var_name = var.as_str().to_string();
+            let value = format_term_with_prefixes(term, prefixes);
+            row.insert(var_name, value);
+        }
+
+        result_rows.push(Value::Object(row));
+    }
+
+    Ok((result_rows, false))
+}
+
+// Convert an RDF term to a JSON value, preserving numeric datatypes
+fn format_term(term: &Term) -> Value {
+    format_term_with_prefixes(term, &BTreeMap::new())
+}
+
+// Like format_term, but IRIs are shortened to `prefix:local` using the longest
+// matching namespace
+fn format_term_with_prefixes(term: &Term, prefixes: &BTreeMap<String, String>) -> Value {
+    match term {
+        Term::NamedNode(n) => {
+            let iri = n.as_str();
+            let shortened = prefixes
+                .iter()
+                .filter(|(_, namespace)| iri.starts_with(namespace.as_str()))
+                .max_by_key(|(_, namespace)| namespace.len())
+                .map(|(prefix, namespace)| format!("{}:{}", prefix, &iri[namespace.len()..]));
+            Value::String(shortened.unwrap_or_else(|| iri.to_string()))
+        }
+        Term::BlankNode(b) => Value::String(format!("_:{}", b.as_str())),
+        Term::Literal(l) => {
+            TypedLiteral::new(l.value(), l.datatype().as_str(), l.language()).to_json_value()
+        }
+        // RDF 1.2 triple terms are rendered as nested objects
+        Term::Triple(t) => json!({
+            "subject": format_term_with_prefixes(&t.subject.clone().into(), prefixes),
+            "predicate": format_term_with_prefixes(&t.predicate.clone().into(), prefixes),
+            "object": format_term_with_prefixes(&t.object, prefixes),
+        }),
+    }
+}
+
 // Load Turtle data into a named store
+// - A store name that is not valid UTF-8 is rejected before any store is touched
+// - Store names are trimmed and lowercased (Unicode-aware) and otherwise used as is:
+//   an empty name is the store "", and characters such as `/` or `:` have no
+//   special meaning. "Größe" and "GRÖSSE" lowercase differently and stay distinct.
+// - Invalid UTF-8 in the data is a Turtle parse error. The load is all or nothing,
+//   but the store is created before parsing, so it exists (possibly empty) afterwards.
 #[wasm_func]
 pub fn load_turtle(store_name: &[u8], turtle_data: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
 
     match with_stores_mut(|stores| {
         let store = get_or_create_store(stores, &store_name)?;
@@ -125,21 +440,32 @@ pub fn load_turtle(store_name: &[u8], turtle_data: &[u8]) -> Vec<u8> {
     }
 }
 
-// Load RDF/XML data into a named store
+// Load Turtle data into a named store, resolving relative IRIs against base_iri.
+// The base must be an absolute IRI; an empty base behaves like load_turtle, where
+// relative IRIs are rejected unless the data declares its own @base.
 #[wasm_func]
-pub fn load_rdf_xml(store_name: &[u8], rdf_xml_data: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
+pub fn load_turtle_with_base_iri(store_name: &[u8], turtle_data: &[u8], base_iri: &[u8]) -> Vec<u8> {
+    if base_iri.is_empty() {
+        return load_turtle(store_name, turtle_data);
+    }
+
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let base_iri = match String::from_utf8(base_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid base IRI: {}", e).into_bytes(),
+    };
+    let parser = match RdfParser::from_format(RdfFormat::Turtle).with_base_iri(base_iri.as_str()) {
+        Ok(parser) => parser,
+        Err(e) => return format!("ERROR: Invalid base IRI '{}': {}", base_iri, e).into_bytes(),
     };
 
     match with_stores_mut(|stores| {
         let store = get_or_create_store(stores, &store_name)?;
 
-        // Parse and load RDF/XML data
         store
-            .load_from_reader(RdfFormat::RdfXml, rdf_xml_data)
-            .map_err(|e| format!("Failed to parse RDF/XML data: {}", e))?;
+            .load_from_reader(parser, turtle_data)
+            .map_err(|e| format!("Failed to parse Turtle data: {}", e))?;
 
         Ok(())
     }) {
@@ -148,227 +474,179 @@ pub fn load_rdf_xml(store_name: &[u8], rdf_xml_data: &[u8]) -> Vec<u8> {
     }
 }
 
-// Load N-Triples data into a named store
-#[wasm_func]
-pub fn load_ntriples(store_name: &[u8], ntriples_data: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
+// Guess the format of RDF data from its first statements: an XML declaration or
+// rdf:RDF root element means RDF/XML, a @prefix/@base (or SPARQL-style PREFIX/BASE)
+// directive means Turtle, and lines that each hold one complete statement of full
+// IRIs and blank node labels mean N-Triples. Anything else is left undecided.
+fn auto_detect_format(data: &[u8]) -> Option<RdfFormat> {
+    let text = core::str::from_utf8(data).ok()?;
+    let text = text.trim_start_matches('\u{feff}').trim_start();
 
-    match with_stores_mut(|stores| {
-        let store = get_or_create_store(stores, &store_name)?;
+    if text.starts_with("<?xml") || text.starts_with("<rdf:RDF") {
+        return Some(RdfFormat::RdfXml);
+    }
 
-        // Parse and load N-Triples data
-        store
-            .load_from_reader(RdfFormat::NTriples, ntriples_data)
-            .map_err(|e| format!("Failed to parse N-Triples data: {}", e))?;
+    let mut statements = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
 
-        Ok(())
-    }) {
-        Ok(_) => b"OK".to_vec(),
-        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    let is_directive = |line: &str| {
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        ["@prefix", "@base"].contains(&keyword)
+            || keyword.eq_ignore_ascii_case("prefix")
+            || keyword.eq_ignore_ascii_case("base")
+    };
+    if statements.clone().any(is_directive) {
+        return Some(RdfFormat::Turtle);
     }
+
+    statements.peek()?;
+    let is_ntriples_line =
+        |line: &str| (line.starts_with('<') || line.starts_with("_:")) && line.ends_with('.');
+    statements.all(is_ntriples_line).then_some(RdfFormat::NTriples)
 }
 
-// Execute SPARQL SELECT query against a named store
+// Load RDF data whose format is detected by auto_detect_format, returning
+// "OK: <format>" with the format that was used. Undetected data is rejected with
+// the list of functions that load a known format.
 #[wasm_func]
-pub fn query(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
+pub fn load_rdf_auto(store_name: &[u8], rdf_data: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
 
-    let sparql = match String::from_utf8(sparql_query.to_vec()) {
-        Ok(query) => query,
-        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    let Some(format) = auto_detect_format(rdf_data) else {
+        return b"ERROR: Could not detect the RDF format, use load_turtle, load_ntriples, load_rdf_xml or load_jsonld"
+            .to_vec();
     };
 
     match with_stores_mut(|stores| {
-        let store = stores
-            .get(&store_name)
-            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
-
-        // Execute SPARQL query
-        let results = store
-            .query(&sparql)
-            .map_err(|e| format!("SPARQL query execution failed: {}", e))?;
-
-        // Convert results to JSON
-        match results {
-            QueryResults::Solutions(solutions) => {
-                let mut result_rows = Vec::new();
-
-                for solution in solutions {
-                    let solution = solution.map_err(|e| format!("Error reading solution: {}", e))?;
-                    let mut row = serde_json::Map::new();
-
-                    for (var, term) in solution.iter() {
-                        let var_name = var.as_str().to_string();
-                        let value = match term {
-                            oxigraph::model::Term::NamedNode(n) => Value::String(n.as_str().to_string()),
-                            oxigraph::model::Term::BlankNode(b) => Value::String(format!("_:{}", b.as_str())),
-                            oxigraph::model::Term::Literal(l) => {
-                                // Try to parse as number if it's an integer/decimal
-                                if l.datatype() == oxigraph::model::vocab::xsd::INTEGER
-                                    || l.datatype() == oxigraph::model::vocab::xsd::INT
-                                    || l.datatype() == oxigraph::model::vocab::xsd::LONG {
-                                    if let Ok(num) = l.value().parse::<i64>() {
-                                        json!(num)
-                                    } else {
-                                        Value::String(l.value().to_string())
-                                    }
-                                } else if l.datatype() == oxigraph::model::vocab::xsd::DECIMAL
-                                    || l.datatype() == oxigraph::model::vocab::xsd::DOUBLE
-                                    || l.datatype() == oxigraph::model::vocab::xsd::FLOAT {
-                                    if let Ok(num) = l.value().parse::<f64>() {
-                                        json!(num)
-                                    } else {
-                                        Value::String(l.value().to_string())
-                                    }
-                                } else if l.datatype() == oxigraph::model::vocab::xsd::BOOLEAN {
-                                    if let Ok(b) = l.value().parse::<bool>() {
-                                        Value::Bool(b)
-                                    } else {
-                                        Value::String(l.value().to_string())
-                                    }
-                                } else {
-                                    Value::String(l.value().to_string())
-                                }
-                            },
-                            _ => Value::String(term.to_string()),
-                        };
-                        row.insert(var_name, value);
-                    }
-
-                    result_rows.push(Value::Object(row));
-                }
-
-                serde_json::to_string(&result_rows)
-                    .map_err(|e| format!("JSON serialization error: {}", e))
-            }
-            QueryResults::Boolean(b) => {
-                Ok(json!({"boolean": b}).to_string())
-            }
-            QueryResults::Graph(_) => {
-                Err("CONSTRUCT queries should use query_construct function".to_string())
-            }
-        }
+        get_or_create_store(stores, &store_name)?
+            .load_from_reader(format, rdf_data)
+            .map_err(|e| format!("Failed to parse {} data (detected format): {}", format.name(), e))
     }) {
-        Ok(json_result) => json_result.into_bytes(),
+        Ok(_) => format!("OK: {}", format.name()).into_bytes(),
         Err(e) => format!("ERROR: {}", e).into_bytes(),
     }
 }
 
-// Execute SPARQL CONSTRUCT query against a named store
+// Load base64-encoded Turtle data into a named store. Whitespace in the input is
+// ignored, so line-wrapped base64 as produced by most encoders is accepted.
 #[wasm_func]
-pub fn query_construct(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
-
-    let sparql = match String::from_utf8(sparql_query.to_vec()) {
-        Ok(query) => query,
-        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
-    };
-
-    match with_stores_mut(|stores| {
-        let store = stores
-            .get(&store_name)
-            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+pub fn load_turtle_base64(store_name: &[u8], base64_turtle_data: &[u8]) -> Vec<u8> {
+    let encoded: Vec<u8> = base64_turtle_data
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    match BASE64_STANDARD.decode(&encoded) {
+        Ok(turtle_data) => load_turtle(store_name, &turtle_data),
+        Err(e) => format!("ERROR: Invalid base64 data: {}", e).into_bytes(),
+    }
+}
 
-        // Execute SPARQL query
-        let results = store
-            .query(&sparql)
-            .map_err(|e| format!("SPARQL query execution failed: {}", e))?;
+// Bring a named store from old_turtle to new_turtle by applying only the triples
+// that differ between the two, instead of clearing and reloading the whole store.
+// Loads rename blank nodes, so triples containing them cannot be matched against
+// the store and such inputs are rejected. Returns {"added": n, "removed": m}.
+#[wasm_func]
+pub fn load_turtle_incremental(store_name: &[u8], old_turtle: &[u8], new_turtle: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
 
-        // Convert graph results to Turtle
-        match results {
-            QueryResults::Graph(triples) => {
-                // Collect all triples and serialize them directly
-                use oxigraph::io::RdfSerializer;
-                let mut output = Vec::new();
-                let mut serializer = RdfSerializer::from_format(RdfFormat::Turtle)
-                    .for_writer(&mut output);
+    let old_quads = match parse_turtle_quad_set(old_turtle, "old") {
+        Ok(quads) => quads,
+        Err(e) => return format!("ERROR: {}", e).into_bytes(),
+    };
+    let new_quads = match parse_turtle_quad_set(new_turtle, "new") {
+        Ok(quads) => quads,
+        Err(e) => return format!("ERROR: {}", e).into_bytes(),
+    };
 
-                for triple_result in triples {
-                    let triple = triple_result.map_err(|e| format!("Error reading triple: {}", e))?;
-                    serializer.serialize_triple(triple.as_ref())
-                        .map_err(|e| format!("Error serializing triple: {}", e))?;
-                }
+    let removed: Vec<&Quad> = old_quads
+        .iter()
+        .filter(|(key, _)| !new_quads.contains_key(*key))
+        .map(|(_, quad)| quad)
+        .collect();
+    let added: Vec<&Quad> = new_quads
+        .iter()
+        .filter(|(key, _)| !old_quads.contains_key(*key))
+        .map(|(_, quad)| quad)
+        .collect();
 
-                serializer.finish().map_err(|e| format!("Error finishing serialization: {}", e))?;
+    match with_stores_mut(|stores| {
+        let store = get_or_create_store(stores, &store_name)?;
 
-                String::from_utf8(output).map_err(|e| format!("UTF-8 error: {}", e))
-            }
-            QueryResults::Solutions(_) => {
-                Err("SELECT queries should use query function".to_string())
-            }
-            QueryResults::Boolean(_) => {
-                Err("ASK queries should use query_ask function".to_string())
-            }
+        // Apply both sides in one transaction so a failure leaves the store as it was
+        let mut transaction = store
+            .start_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        for quad in &removed {
+            transaction.remove(*quad);
         }
+        for quad in &added {
+            transaction.insert(*quad);
+        }
+        transaction
+            .commit()
+            .map_err(|e| format!("Failed to apply changes: {}", e))?;
+
+        Ok(())
     }) {
-        Ok(turtle_result) => turtle_result.into_bytes(),
+        Ok(_) => json!({ "added": added.len(), "removed": removed.len() })
+            .to_string()
+            .into_bytes(),
         Err(e) => format!("ERROR: {}", e).into_bytes(),
     }
 }
 
-// Execute SPARQL ASK query against a named store
-#[wasm_func]
-pub fn query_ask(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
+// Parse Turtle into a set of quads keyed by their N-Quads form
+fn parse_turtle_quad_set(data: &[u8], label: &str) -> Result<BTreeMap<String, Quad>, String> {
+    let mut quads = BTreeMap::new();
+    for quad in RdfParser::from_format(RdfFormat::Turtle).for_slice(data) {
+        let quad = quad.map_err(|e| format!("Failed to parse {} Turtle data: {}", label, e))?;
+        if quad.subject.is_blank_node() || quad.object.is_blank_node() {
+            return Err(format!(
+                "The {} Turtle data contains blank nodes, which cannot be loaded incrementally; use clear_store and load_turtle instead",
+                label
+            ));
+        }
+        quads.insert(quad.to_string(), quad);
+    }
+    Ok(quads)
+}
 
-    let sparql = match String::from_utf8(sparql_query.to_vec()) {
-        Ok(query) => query,
-        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
-    };
+// Load RDF/XML data into a named store
+#[wasm_func]
+pub fn load_rdf_xml(store_name: &[u8], rdf_xml_data: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
 
     match with_stores_mut(|stores| {
-        let store = stores
-            .get(&store_name)
-            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let store = get_or_create_store(stores, &store_name)?;
 
-        // Execute SPARQL query
-        let results = store
-            .query(&sparql)
-            .map_err(|e| format!("SPARQL query execution failed: {}", e))?;
+        // Parse and load RDF/XML data
+        store
+            .load_from_reader(RdfFormat::RdfXml, rdf_xml_data)
+            .map_err(|e| format!("Failed to parse RDF/XML data: {}", e))?;
 
-        // Get boolean result
-        match results {
-            QueryResults::Boolean(b) => Ok(if b { "true" } else { "false" }.to_string()),
-            QueryResults::Solutions(_) => {
-                Err("SELECT queries should use query function".to_string())
-            }
-            QueryResults::Graph(_) => {
-                Err("CONSTRUCT queries should use query_construct function".to_string())
-            }
-        }
+        Ok(())
     }) {
-        Ok(result) => result.into_bytes(),
+        Ok(_) => b"OK".to_vec(),
         Err(e) => format!("ERROR: {}", e).into_bytes(),
     }
 }
 
-// Clear all data from a store
+// Load N-Triples data into a named store
 #[wasm_func]
-pub fn clear_store(store_name: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
-    };
+pub fn load_ntriples(store_name: &[u8], ntriples_data: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
 
     match with_stores_mut(|stores| {
-        let store = stores
-            .get_mut(&store_name)
-            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let store = get_or_create_store(stores, &store_name)?;
 
-        // Clear the store by removing all quads
-        store.clear().map_err(|e| format!("Failed to clear store: {}", e))?;
+        // Parse and load N-Triples data
+        store
+            .load_from_reader(RdfFormat::NTriples, ntriples_data)
+            .map_err(|e| format!("Failed to parse N-Triples data: {}", e))?;
 
         Ok(())
     }) {
@@ -377,25 +655,1261 @@ pub fn clear_store(store_name: &[u8]) -> Vec<u8> {
     }
 }
 
-// List all available stores
+// Load N-Triples data into a named store through oxigraph's bulk loader, which
+// skips the per-triple transaction overhead of load_ntriples. Inputs smaller than
+// BULK_LOAD_THRESHOLD gain nothing from it and go through load_ntriples.
+// The bulk loader only writes the triples on commit, so a parse error leaves the
+// store untouched.
 #[wasm_func]
-pub fn list_stores() -> Vec<u8> {
-    match with_stores_mut(|stores| {
-        let store_names: Vec<String> = stores.keys().cloned().collect();
-        serde_json::to_string(&store_names)
-            .map_err(|e| format!("JSON serialization error: {}", e))
-    }) {
-        Ok(json_result) => json_result.into_bytes(),
-        Err(e) => format!("ERROR: {}", e).into_bytes(),
+pub fn load_ntriples_bulk(store_name: &[u8], ntriples_data: &[u8]) -> Vec<u8> {
+    if ntriples_data.len() < BULK_LOAD_THRESHOLD {
+        return load_ntriples(store_name, ntriples_data);
     }
-}
 
-// Get the size of a store (number of triples)
-#[wasm_func]
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = get_or_create_store(stores, &store_name)?;
+
+        let mut loader = store.bulk_loader();
+        loader
+            .load_from_reader(RdfFormat::NTriples, ntriples_data)
+            .map_err(|e| format!("Failed to parse N-Triples data: {}", e))?;
+        loader
+            .commit()
+            .map_err(|e| format!("Failed to commit bulk load: {}", e))
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Start loading Turtle data in chunks, so that large files never have to be held
+// in plugin memory at once. Returns the session ID to pass to load_turtle_chunk
+// and load_turtle_end. Triples are inserted as chunks complete them, so a chunk
+// that fails to parse leaves the triples of the earlier chunks in the store.
+#[wasm_func]
+pub fn load_turtle_begin(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    if let Err(e) = with_stores_mut(|stores| get_or_create_store(stores, &store_name).map(|_| ())) {
+        return format!("ERROR: {}", e).into_bytes();
+    }
+
+    let session_id = NEXT_LOAD_SESSION.fetch_add(1, Ordering::Relaxed).to_string();
+    let session = LoadSession {
+        store_name,
+        parser: TurtleParser::new().low_level(),
+        loaded: 0,
+        blank_nodes: BTreeMap::new(),
+    };
+    with_load_sessions_mut(|sessions| sessions.insert(session_id.clone(), session));
+    session_id.into_bytes()
+}
+
+// Feed the next chunk of a load session. Chunks may split the data anywhere, even
+// inside a statement; every triple completed by this chunk is inserted right away.
+#[wasm_func]
+pub fn load_turtle_chunk(session_id: &[u8], chunk: &[u8]) -> Vec<u8> {
+    match feed_load_session(session_id, Some(chunk)) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Finish a load session, returning "OK: <number of triples loaded>"
+#[wasm_func]
+pub fn load_turtle_end(session_id: &[u8]) -> Vec<u8> {
+    match feed_load_session(session_id, None) {
+        Ok(loaded) => format!("OK: {}", loaded).into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Parses whatever the new chunk completes, or the rest of the data when the chunk
+// is None. The session is closed when it ends or fails; triples inserted by
+// earlier chunks stay in the store.
+fn feed_load_session(session_id: &[u8], chunk: Option<&[u8]>) -> Result<usize, String> {
+    let session_id = String::from_utf8(session_id.to_vec()).map_err(|e| format!("Invalid session ID: {}", e))?;
+    let mut session = with_load_sessions_mut(|sessions| sessions.remove(&session_id))
+        .ok_or_else(|| format!("Load session '{}' not found", session_id))?;
+
+    match chunk {
+        Some(chunk) => session.parser.extend_from_slice(chunk),
+        None => session.parser.end(),
+    }
+
+    let mut quads = Vec::new();
+    while let Some(triple) = session.parser.parse_next() {
+        let triple = triple.map_err(|e| format!("Failed to parse Turtle data: {}", e))?;
+        quads.push(session.rename_triple(triple).in_graph(GraphName::DefaultGraph));
+    }
+    session.loaded += quads.len();
+
+    with_stores_mut(|stores| {
+        get_or_create_store(stores, &session.store_name)?
+            .extend(quads)
+            .map_err(|e| format!("Failed to insert triples: {}", e))
+    })?;
+
+    let loaded = session.loaded;
+    if chunk.is_some() {
+        with_load_sessions_mut(|sessions| sessions.insert(session_id, session));
+    }
+    Ok(loaded)
+}
+
+// Load Turtle data into a named graph of a named store
+#[wasm_func]
+pub fn load_turtle_with_graph(store_name: &[u8], turtle_data: &[u8], graph_iri: &[u8]) -> Vec<u8> {
+    load_into_graph(store_name, turtle_data, graph_iri, RdfFormat::Turtle, "Turtle")
+}
+
+// Load RDF/XML data into a named graph of a named store
+#[wasm_func]
+pub fn load_rdf_xml_with_graph(store_name: &[u8], rdf_xml_data: &[u8], graph_iri: &[u8]) -> Vec<u8> {
+    load_into_graph(store_name, rdf_xml_data, graph_iri, RdfFormat::RdfXml, "RDF/XML")
+}
+
+// Load N-Triples data into a named graph of a named store
+#[wasm_func]
+pub fn load_ntriples_with_graph(store_name: &[u8], ntriples_data: &[u8], graph_iri: &[u8]) -> Vec<u8> {
+    load_into_graph(store_name, ntriples_data, graph_iri, RdfFormat::NTriples, "N-Triples")
+}
+
+// Parse triples and insert them into the given graph instead of the default graph,
+// keeping track of which source each triple came from
+fn load_into_graph(store_name: &[u8], data: &[u8], graph_iri: &[u8], format: RdfFormat, format_name: &str) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let graph = match String::from_utf8(graph_iri.to_vec()) {
+        Ok(iri) => match NamedNode::new(iri) {
+            Ok(graph) => graph,
+            Err(e) => return format!("ERROR: Invalid graph IRI: {}", e).into_bytes(),
+        },
+        Err(e) => return format!("ERROR: Invalid graph IRI: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = get_or_create_store(stores, &store_name)?;
+
+        let parser = RdfParser::from_format(format).with_default_graph(graph);
+        store
+            .load_from_reader(parser, data)
+            .map_err(|e| format!("Failed to parse {} data: {}", format_name, e))?;
+
+        Ok(())
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Whether a JSON-LD document refers to a context by IRI, in @context or in the
+// @import of a context, which would have to be fetched
+fn references_remote_context(value: &Value) -> bool {
+    let is_reference = |context: &Value| match context {
+        Value::String(_) => true,
+        Value::Array(items) => items.iter().any(Value::is_string),
+        _ => false,
+    };
+    match value {
+        Value::Object(object) => object.iter().any(|(key, value)| {
+            (matches!(key.as_str(), "@context" | "@import") && is_reference(value)) || references_remote_context(value)
+        }),
+        Value::Array(items) => items.iter().any(references_remote_context),
+        _ => false,
+    }
+}
+
+// Load JSON-LD data into a named store
+// Remote @context references are rejected since the plugin has no network access
+#[wasm_func]
+pub fn load_jsonld(store_name: &[u8], jsonld_data: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = get_or_create_store(stores, &store_name)?;
+
+        // Parse and load JSON-LD data, no document loader is configured so remote contexts fail
+        let format = RdfFormat::JsonLd { profile: JsonLdProfileSet::empty() };
+        store.load_from_reader(format, jsonld_data).map_err(|e| {
+            let remote = serde_json::from_slice::<Value>(jsonld_data).is_ok_and(|document| references_remote_context(&document));
+            let hint = if remote { " (remote contexts are not supported)" } else { "" };
+            format!("Failed to parse JSON-LD data{}: {}", hint, e)
+        })?;
+
+        Ok(())
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute SPARQL SELECT query against a named store
+#[wasm_func]
+pub fn query(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        // Execute SPARQL query
+        let results = run_query(store, &sparql, stores)?;
+
+        // Registered prefixes are used to shorten IRIs in the output
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+
+        results_to_json(results, &prefixes)
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Load Turtle data into a temporary store, run a SPARQL query against it and drop
+// the store, for one-off queries that do not need a named store
+#[wasm_func]
+pub fn query_inline(turtle_data: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let result = Store::new()
+        .map_err(|e| format!("Failed to create store: {}", e))
+        .and_then(|store| {
+            store
+                .load_from_reader(RdfFormat::Turtle, turtle_data)
+                .map_err(|e| format!("Failed to parse Turtle data: {}", e))?;
+            results_to_json(run_query(&store, &sparql, &BTreeMap::new())?, &BTreeMap::new())
+        });
+
+    match result {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute a SPARQL SELECT query, keeping at most max_rows rows (a decimal number).
+// The rows are wrapped in an object that records whether the result was cut off:
+// {"rows": [...], "truncated": true, "total_returned": 100}
+// ASK queries return {"boolean": ...} as with query.
+#[wasm_func]
+pub fn query_limited(store_name: &[u8], sparql_query: &[u8], max_rows: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let max_rows = match core::str::from_utf8(max_rows).ok().and_then(|n| n.trim().parse::<usize>().ok()) {
+        Some(max_rows) => max_rows,
+        None => return b"ERROR: Row limit must be a non-negative integer".to_vec(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+
+        match run_query(store, &sparql, stores)? {
+            QueryResults::Solutions(solutions) => {
+                let (rows, truncated) = solutions_to_rows(solutions, &prefixes, max_rows)?;
+                Ok(json!({
+                    "total_returned": rows.len(),
+                    "truncated": truncated,
+                    "rows": rows,
+                })
+                .to_string())
+            }
+            results => results_to_json(results, &prefixes),
+        }
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute a SPARQL SELECT query and keep its rows for query_paged_next, returning
+// the cursor ID. The rows are subject to the same limit as query.
+#[wasm_func]
+pub fn query_paged_open(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let rows = with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+
+        match run_query(store, &sparql, stores)? {
+            QueryResults::Solutions(solutions) => {
+                let (rows, truncated) = solutions_to_rows(solutions, &prefixes, MAX_QUERY_ROWS)?;
+                if truncated {
+                    return Err(format!("Query returned more than {} rows, add a LIMIT", MAX_QUERY_ROWS));
+                }
+                Ok(rows)
+            }
+            _ => Err("Only SELECT queries can be paged".to_string()),
+        }
+    });
+
+    match rows {
+        Ok(rows) => {
+            let cursor_id = NEXT_CURSOR.fetch_add(1, Ordering::Relaxed).to_string();
+            with_pagination_states_mut(|states| {
+                states.insert(cursor_id.clone(), PaginationState { rows, position: 0 })
+            });
+            cursor_id.into_bytes()
+        }
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Return the next page_size rows (a decimal number) of a cursor as
+// {"rows": [...], "remaining": 42}. Once every row has been read, further calls
+// return an empty page; the cursor stays open until query_paged_close.
+#[wasm_func]
+pub fn query_paged_next(cursor_id: &[u8], page_size: &[u8]) -> Vec<u8> {
+    let cursor_id = match String::from_utf8(cursor_id.to_vec()) {
+        Ok(id) => id,
+        Err(e) => return format!("ERROR: Invalid cursor ID: {}", e).into_bytes(),
+    };
+
+    let page_size = match core::str::from_utf8(page_size).ok().and_then(|n| n.trim().parse::<usize>().ok()) {
+        Some(page_size) => page_size,
+        None => return b"ERROR: Page size must be a non-negative integer".to_vec(),
+    };
+
+    with_pagination_states_mut(|states| match states.get_mut(&cursor_id) {
+        Some(state) => {
+            let end = state.rows.len().min(state.position + page_size);
+            let page = &state.rows[state.position..end];
+            state.position = end;
+            json!({ "rows": page, "remaining": state.rows.len() - end })
+                .to_string()
+                .into_bytes()
+        }
+        None => format!("ERROR: Cursor '{}' not found", cursor_id).into_bytes(),
+    })
+}
+
+// Release the rows kept for a cursor
+#[wasm_func]
+pub fn query_paged_close(cursor_id: &[u8]) -> Vec<u8> {
+    let cursor_id = match String::from_utf8(cursor_id.to_vec()) {
+        Ok(id) => id,
+        Err(e) => return format!("ERROR: Invalid cursor ID: {}", e).into_bytes(),
+    };
+
+    match with_pagination_states_mut(|states| states.remove(&cursor_id)) {
+        Some(_) => b"OK".to_vec(),
+        None => format!("ERROR: Cursor '{}' not found", cursor_id).into_bytes(),
+    }
+}
+
+// Parse a SPARQL query without executing it and return its algebra in the SPARQL
+// S-Expression syntax (https://jena.apache.org/documentation/notes/sse.html), e.g.
+// (project (?name) (bgp (triple ?p <http://xmlns.com/foaf/0.1/name> ?name))).
+// Shows how property paths, aggregates, subqueries and OPTIONAL/FILTER scoping
+// were understood, which explains many unexpected results.
+#[wasm_func]
+pub fn query_explain(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    // The store is not read, but a missing store is reported as it would be by query
+    match with_stores_mut(|stores| {
+        if !stores.contains_key(&store_name) {
+            return Err(format!("Store '{}' not found", store_name));
+        }
+        spargebra::SparqlParser::new()
+            .parse_query(&sparql)
+            .map(|query| query.to_sse())
+            .map_err(|e| format!("SPARQL query parsing failed: {}", e))
+    }) {
+        Ok(algebra) => algebra.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute a SPARQL query with evaluation options given as a JSON object:
+// - "base_iri": IRI against which relative IRIs in the query are resolved
+// - "default_graph": IRI of the named graph used as the default graph, replacing
+//   any FROM clause of the query
+// - "timeout_ms": accepted for compatibility with the CLI, but Typst gives plugins
+//   no clock to measure it with, so any value is rejected
+// As with query, SERVICE calls can only target plugin stores through store:// IRIs.
+#[wasm_func]
+pub fn query_with_options(store_name: &[u8], sparql_query: &[u8], options_json: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let options: serde_json::Map<String, Value> = match serde_json::from_slice(options_json) {
+        Ok(options) => options,
+        Err(e) => return format!("ERROR: Options must be a JSON object: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let mut evaluator = SparqlEvaluator::new().with_default_service_handler(LocalStoreServices::new(stores));
+        let mut default_graph = None;
+        for (key, value) in &options {
+            match (key.as_str(), value) {
+                ("base_iri", Value::String(iri)) => {
+                    evaluator = evaluator
+                        .with_base_iri(iri.as_str())
+                        .map_err(|e| format!("Invalid base IRI '{}': {}", iri, e))?;
+                }
+                ("default_graph", Value::String(iri)) => {
+                    let graph = NamedNode::new(iri.as_str())
+                        .map_err(|e| format!("Invalid default graph IRI '{}': {}", iri, e))?;
+                    default_graph = Some(GraphName::NamedNode(graph));
+                }
+                ("timeout_ms", Value::Number(_)) => {
+                    return Err("timeout_ms is not supported: plugins have no clock".to_string());
+                }
+                ("base_iri" | "default_graph", _) => return Err(format!("Option '{}' must be a string", key)),
+                ("timeout_ms", _) => return Err("Option 'timeout_ms' must be a number".to_string()),
+                _ => return Err(format!("Unknown query option '{}'", key)),
+            }
+        }
+
+        let mut prepared = evaluator
+            .parse_query(&sparql)
+            .map_err(|e| format!("SPARQL query parsing failed: {}", e))?;
+        if let Some(graph) = default_graph {
+            prepared.dataset_mut().set_default_graph(alloc::vec![graph]);
+        }
+        let results = prepared
+            .on_store(store)
+            .execute()
+            .map_err(|e| format!("SPARQL query execution failed: {}", e))?;
+
+        // Registered prefixes are used to shorten IRIs in the output
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+
+        results_to_json(results, &prefixes)
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Describe the variables produced by a SELECT query
+// Only the first SCHEMA_SAMPLE_SIZE solutions are inspected, the result maps each variable
+// to its most frequent term kind ("IRI", "Literal", "BlankNode") and literal datatype
+#[wasm_func]
+pub fn query_results_schema(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let solutions = match run_query(store, &sparql, stores)? {
+            QueryResults::Solutions(solutions) => solutions,
+            _ => return Err("Only SELECT queries have a result schema".to_string()),
+        };
+
+        // Count term kinds and literal datatypes per variable
+        let variables: Vec<String> = solutions
+            .variables()
+            .iter()
+            .map(|v| v.as_str().to_string())
+            .collect();
+        let mut kinds: BTreeMap<String, BTreeMap<&'static str, usize>> = BTreeMap::new();
+        let mut datatypes: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+
+        for solution in solutions.take(SCHEMA_SAMPLE_SIZE) {
+            let solution = solution.map_err(|e| format!("Error reading solution: {}", e))?;
+            for (var, term) in solution.iter() {
+                let kind = match term {
+                    Term::NamedNode(_) => "IRI",
+                    Term::BlankNode(_) => "BlankNode",
+                    Term::Literal(l) => {
+                        *datatypes
+                            .entry(var.as_str().to_string())
+                            .or_default()
+                            .entry(l.datatype().as_str().to_string())
+                            .or_default() += 1;
+                        "Literal"
+                    }
+                    Term::Triple(_) => "Triple",
+                };
+                *kinds.entry(var.as_str().to_string()).or_default().entry(kind).or_default() += 1;
+            }
+        }
+
+        let mut schema = serde_json::Map::new();
+        for var in variables {
+            let mut entry = serde_json::Map::new();
+            let kind = kinds
+                .get(&var)
+                .and_then(|counts| counts.iter().max_by_key(|(_, count)| **count))
+                .map(|(kind, _)| Value::String(kind.to_string()))
+                .unwrap_or(Value::Null);
+            entry.insert("kind".to_string(), kind);
+            if let Some(datatype) = datatypes
+                .get(&var)
+                .and_then(|counts| counts.iter().max_by_key(|(_, count)| **count))
+            {
+                entry.insert("datatype".to_string(), Value::String(datatype.0.clone()));
+            }
+            schema.insert(var, Value::Object(entry));
+        }
+
+        serde_json::to_string(&schema)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute SPARQL CONSTRUCT query against a named store
+#[wasm_func]
+pub fn query_construct(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        // Execute SPARQL query
+        let results = run_query(store, &sparql, stores)?;
+
+        // Convert graph results to Turtle
+        match results {
+            QueryResults::Graph(triples) => {
+                // Collect all triples and serialize them directly
+                use oxigraph::io::RdfSerializer;
+                let mut output = Vec::new();
+                let mut serializer = RdfSerializer::from_format(RdfFormat::Turtle)
+                    .for_writer(&mut output);
+
+                for triple_result in triples {
+                    let triple = triple_result.map_err(|e| format!("Error reading triple: {}", e))?;
+                    serializer.serialize_triple(triple.as_ref())
+                        .map_err(|e| format!("Error serializing triple: {}", e))?;
+                }
+
+                serializer.finish().map_err(|e| format!("Error finishing serialization: {}", e))?;
+
+                String::from_utf8(output).map_err(|e| format!("UTF-8 error: {}", e))
+            }
+            QueryResults::Solutions(_) => {
+                Err("SELECT queries should use query function".to_string())
+            }
+            QueryResults::Boolean(_) => {
+                Err("ASK queries should use query_ask function".to_string())
+            }
+        }
+    }) {
+        Ok(turtle_result) => turtle_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Serialize the default graph of a store as JSON-LD. The context is a JSON object,
+// optionally wrapped in {"@context": ...}, whose string entries are declared as
+// prefixes in the output context and whose "@base" entry relativizes node IRIs.
+// Other term definitions can't be expressed by the streaming serializer and are
+// rejected. Empty context bytes give expanded JSON-LD without a context.
+#[wasm_func]
+pub fn export_jsonld(store_name: &[u8], context_json: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let serializer = match jsonld_serializer(context_json) {
+        Ok(serializer) => serializer,
+        Err(e) => return format!("ERROR: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let mut serializer = serializer.for_writer(Vec::new());
+        for quad in store.quads_for_pattern(None, None, None, Some(GraphNameRef::DefaultGraph)) {
+            let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+            serializer
+                .serialize_quad(&quad)
+                .map_err(|e| format!("Error serializing quad: {}", e))?;
+        }
+        let output = serializer
+            .finish()
+            .map_err(|e| format!("Error finishing serialization: {}", e))?;
+
+        String::from_utf8(output).map_err(|e| format!("UTF-8 error: {}", e))
+    }) {
+        Ok(jsonld) => jsonld.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Build a JSON-LD serializer from the context accepted by export_jsonld
+fn jsonld_serializer(context_json: &[u8]) -> Result<JsonLdSerializer, String> {
+    let mut serializer = JsonLdSerializer::new();
+    if context_json.iter().all(|byte| byte.is_ascii_whitespace()) {
+        return Ok(serializer);
+    }
+
+    let mut context: serde_json::Map<String, Value> = serde_json::from_slice(context_json)
+        .map_err(|e| format!("Context must be a JSON object: {}", e))?;
+    if let Some(inner) = context.remove("@context") {
+        context = match inner {
+            Value::Object(inner) => inner,
+            _ => return Err("The @context entry must be a JSON object".to_string()),
+        };
+    }
+
+    for (term, definition) in context {
+        let Value::String(iri) = definition else {
+            return Err(format!("Unsupported definition for '{}': only prefix IRIs are supported", term));
+        };
+        serializer = match term.as_str() {
+            "@base" => serializer.with_base_iri(iri),
+            _ if term.starts_with('@') => return Err(format!("Unsupported context keyword '{}'", term)),
+            _ => serializer.with_prefix(term.as_str(), iri),
+        }
+        .map_err(|e| format!("Invalid IRI for '{}': {}", term, e))?;
+    }
+    Ok(serializer)
+}
+
+// Execute a CONSTRUCT query and insert the resulting triples into another store
+// Triples are inserted in a single transaction, without going through Turtle serialization
+// Returns "OK: <n>" where n counts only the triples that were not already in the target
+#[wasm_func]
+pub fn query_to_store(source_store: &[u8], sparql_construct: &[u8], target_store: &[u8]) -> Vec<u8> {
+    let source_name = match store_name_arg_for(source_store, "source store name") { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_construct.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let target_name = match store_name_arg_for(target_store, "target store name") { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let source = stores
+            .get(&source_name)
+            .ok_or_else(|| format!("Store '{}' not found", source_name))?;
+
+        // Collect the constructed triples before touching the target store
+        let quads = match run_query(source, &sparql, stores)? {
+            QueryResults::Graph(triples) => triples
+                .map(|triple| {
+                    triple
+                        .map(|t| t.in_graph(GraphName::DefaultGraph))
+                        .map_err(|e| format!("Error reading triple: {}", e))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            QueryResults::Solutions(_) => {
+                return Err("SELECT queries should use query function".to_string())
+            }
+            QueryResults::Boolean(_) => {
+                return Err("ASK queries should use query_ask function".to_string())
+            }
+        };
+
+        let target = get_or_create_store(stores, &target_name)?;
+        let size_before = target.len().map_err(|e| format!("Failed to get store size: {}", e))?;
+        target
+            .extend(quads)
+            .map_err(|e| format!("Failed to insert triples: {}", e))?;
+        let size_after = target.len().map_err(|e| format!("Failed to get store size: {}", e))?;
+
+        Ok(size_after - size_before)
+    }) {
+        Ok(count) => format!("OK: {}", count).into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute a SPARQL SELECT query and return the results as RFC 4180 CSV, with a
+// header row of variable names, for Typst's csv.decode. Cells hold the same values
+// as query, with prefixes applied; unbound variables give empty cells.
+#[wasm_func]
+pub fn query_to_csv(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let solutions = match run_query(store, &sparql, stores)? {
+            QueryResults::Solutions(solutions) => solutions,
+            _ => return Err("query_to_csv only supports SELECT queries".to_string()),
+        };
+
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+        let variables: Vec<String> = solutions.variables().iter().map(|v| v.as_str().to_string()).collect();
+        let (rows, truncated) = solutions_to_rows(solutions, &prefixes, MAX_QUERY_ROWS)?;
+        if truncated {
+            return Err(format!("Query returned more than {} rows, add a LIMIT", MAX_QUERY_ROWS));
+        }
+
+        let mut csv = String::new();
+        write_csv_record(&mut csv, variables.iter().map(String::as_str));
+        for row in &rows {
+            let cells: Vec<String> = variables
+                .iter()
+                .map(|var| match row.get(var) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect();
+            write_csv_record(&mut csv, cells.iter().map(String::as_str));
+        }
+        Ok(csv)
+    }) {
+        Ok(csv) => csv.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute a SPARQL SELECT query and return the results as a Typst array of
+// dictionaries, for `eval()`. Values are the same as in query's JSON, written as
+// Typst literals: strings are escaped, numbers become integers or floats, and
+// unbound variables are left out of the row like in the JSON results.
+#[wasm_func]
+pub fn query_to_typst_dict(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let solutions = match run_query(store, &sparql, stores)? {
+            QueryResults::Solutions(solutions) => solutions,
+            _ => return Err("query_to_typst_dict only supports SELECT queries".to_string()),
+        };
+
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+        let (rows, truncated) = solutions_to_rows(solutions, &prefixes, MAX_QUERY_ROWS)?;
+        if truncated {
+            return Err(format!("Query returned more than {} rows, add a LIMIT", MAX_QUERY_ROWS));
+        }
+
+        let mut typst = String::new();
+        write_typst_value(&mut typst, &Value::Array(rows));
+        Ok(typst)
+    }) {
+        Ok(typst) => typst.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Write a JSON value as a Typst expression. One-element arrays need a trailing
+// comma to be arrays rather than parenthesized values, and `(:)` is the empty
+// dictionary.
+fn write_typst_value(typst: &mut String, value: &Value) {
+    match value {
+        Value::Null => typst.push_str("none"),
+        Value::Bool(b) => typst.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => typst.push_str(&i.to_string()),
+            // Debug formatting always keeps a `.` or an exponent, so Typst reads a float
+            (None, Some(f)) => typst.push_str(&format!("{:?}", f)),
+            (None, None) => typst.push_str(&n.to_string()),
+        },
+        Value::String(s) => write_typst_string(typst, s),
+        Value::Array(items) => {
+            typst.push('(');
+            for item in items {
+                write_typst_value(typst, item);
+                typst.push_str(", ");
+            }
+            typst.push(')');
+        }
+        Value::Object(entries) if entries.is_empty() => typst.push_str("(:)"),
+        Value::Object(entries) => {
+            typst.push('(');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    typst.push_str(", ");
+                }
+                write_typst_string(typst, key);
+                typst.push_str(": ");
+                write_typst_value(typst, item);
+            }
+            typst.push(')');
+        }
+    }
+}
+
+fn write_typst_string(typst: &mut String, s: &str) {
+    typst.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => typst.push_str("\\\\"),
+            '"' => typst.push_str("\\\""),
+            '\n' => typst.push_str("\\n"),
+            '\r' => typst.push_str("\\r"),
+            '\t' => typst.push_str("\\t"),
+            c if c.is_control() => typst.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => typst.push(c),
+        }
+    }
+    typst.push('"');
+}
+
+// Append one CSV record terminated by CRLF, quoting fields that contain a comma,
+// a double quote or a line break, with embedded quotes doubled
+fn write_csv_record<'a>(csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+// Execute SPARQL ASK query against a named store
+#[wasm_func]
+pub fn query_ask(store_name: &[u8], sparql_query: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        // Execute SPARQL query
+        let results = run_query(store, &sparql, stores)?;
+
+        // Get boolean result
+        match results {
+            QueryResults::Boolean(b) => Ok(if b { "true" } else { "false" }.to_string()),
+            QueryResults::Solutions(_) => {
+                Err("SELECT queries should use query function".to_string())
+            }
+            QueryResults::Graph(_) => {
+                Err("CONSTRUCT queries should use query_construct function".to_string())
+            }
+        }
+    }) {
+        Ok(result) => result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Execute several ASK queries against a named store in one call. The queries are
+// given as a JSON object mapping names to ASK queries, and the result maps the
+// same names to booleans: {"hasAuthor": true, "hasTitle": false}
+// The first failing query fails the whole call, with its name in the error.
+#[wasm_func]
+pub fn query_ask_multi(store_name: &[u8], queries_json: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let queries: BTreeMap<String, String> = match serde_json::from_slice(queries_json) {
+        Ok(queries) => queries,
+        Err(e) => {
+            return format!("ERROR: Queries must be a JSON object mapping names to ASK queries: {}", e)
+                .into_bytes()
+        }
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let mut answers = serde_json::Map::new();
+        for (name, sparql) in &queries {
+            let answer = match run_query(store, sparql, stores).map_err(|e| format!("Query '{}': {}", name, e))? {
+                QueryResults::Boolean(b) => b,
+                _ => return Err(format!("Query '{}' is not an ASK query", name)),
+            };
+            answers.insert(name.clone(), Value::Bool(answer));
+        }
+
+        serde_json::to_string(&answers)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Create an empty store with options given as a JSON object, failing if the store
+// already exists instead of reusing it like the load functions do:
+// - "initial_capacity_hint": expected number of triples. Oxigraph's in-memory store
+//   has no way to pre-allocate, so the hint is validated but has no effect yet.
+// Unknown options are rejected so that a misspelled option is not silently ignored.
+#[wasm_func]
+pub fn create_store_with_options(store_name: &[u8], options_json: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let options: serde_json::Map<String, Value> = match serde_json::from_slice(options_json) {
+        Ok(options) => options,
+        Err(e) => return format!("ERROR: Options must be a JSON object: {}", e).into_bytes(),
+    };
+
+    for (key, value) in &options {
+        match (key.as_str(), value) {
+            ("initial_capacity_hint", value) if value.is_u64() => {}
+            ("initial_capacity_hint", _) => {
+                return b"ERROR: Option 'initial_capacity_hint' must be a non-negative integer".to_vec();
+            }
+            _ => return format!("ERROR: Unknown store option '{}'", key).into_bytes(),
+        }
+    }
+
+    match with_stores_mut(|stores| {
+        if stores.contains_key(&store_name) {
+            return Err(format!("Store '{}' already exists", store_name));
+        }
+        get_or_create_store(stores, &store_name)?;
+        Ok(())
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Clear all data from a store
+#[wasm_func]
+pub fn clear_store(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get_mut(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        // Clear the store by removing all quads
+        store.clear().map_err(|e| format!("Failed to clear store: {}", e))?;
+
+        Ok(())
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Copy all quads of a store into another store, creating the target if needed
+// Quads already present in the target are merged following set semantics
+#[wasm_func]
+pub fn copy_store(source_name: &[u8], target_name: &[u8]) -> Vec<u8> {
+    let source_name = match store_name_arg_for(source_name, "source store name") { Ok(name) => name, Err(e) => return e };
+
+    let target_name = match store_name_arg_for(target_name, "target store name") { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        // Store handles are cheap to clone and share the same underlying data
+        let source = stores
+            .get(&source_name)
+            .cloned()
+            .ok_or_else(|| format!("Store '{}' not found", source_name))?;
+        let target = get_or_create_store(stores, &target_name)?;
+
+        merge_quads(&source, target)
+    }) {
+        Ok(copied) => format!("OK: {}", copied).into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Report the plugin version and how it was built, as a JSON object with the same
+// fields as `typox info`: typox, api, oxigraph, rustc, target and profile
+#[wasm_func]
+pub fn get_version() -> Vec<u8> {
+    json!({
+        "typox": env!("CARGO_PKG_VERSION"),
+        "api": API_VERSION,
+        "oxigraph": env!("TYPOX_OXIGRAPH_VERSION"),
+        "rustc": env!("TYPOX_RUSTC_VERSION"),
+        "target": env!("TYPOX_TARGET"),
+        "profile": env!("TYPOX_PROFILE"),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+// Report the version of the JSON contract, e.g. "1.0.0", for packages to check
+// that they understand this plugin's output
+#[wasm_func]
+pub fn get_api_version() -> Vec<u8> {
+    API_VERSION.as_bytes().to_vec()
+}
+
+// List all available stores
+#[wasm_func]
+pub fn list_stores() -> Vec<u8> {
+    match with_stores_mut(|stores| {
+        let store_names: Vec<String> = stores.keys().cloned().collect();
+        serde_json::to_string(&store_names)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// List all stores with their number of triples, in one call:
+// [{"name": "memory", "size": 42}, {"name": "ontology", "size": 1500}]
+#[wasm_func]
+pub fn list_stores_with_sizes() -> Vec<u8> {
+    match with_stores_mut(|stores| {
+        let mut entries = Vec::with_capacity(stores.len());
+        for (name, store) in stores.iter() {
+            let size = store.len().map_err(|e| format!("Failed to get size of store '{}': {}", name, e))?;
+            entries.push(json!({ "name": name, "size": size }));
+        }
+        serde_json::to_string(&entries)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Check whether a named store has been created
+// Returns "true" or "false" so the result can be used directly in Typst conditionals
+#[wasm_func]
+pub fn store_exists(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| Ok(stores.contains_key(&store_name))) {
+        Ok(true) => b"true".to_vec(),
+        Ok(false) => b"false".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Get the size of a store (number of triples)
+#[wasm_func]
 pub fn get_store_size(store_name: &[u8]) -> Vec<u8> {
-    let store_name = match String::from_utf8(store_name.to_vec()) {
-        Ok(name) => name,
-        Err(e) => return format!("ERROR: Invalid store name: {}", e).into_bytes(),
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        // Count quads in the store
+        let count = store.len().map_err(|e| format!("Failed to get store size: {}", e))?;
+
+        Ok(count.to_string())
+    }) {
+        Ok(size_str) => size_str.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Get metadata about a store: size, named graphs and distinct term counts
+#[wasm_func]
+pub fn get_store_info(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let size = store.len().map_err(|e| format!("Failed to get store size: {}", e))?;
+
+        let mut graphs = Vec::new();
+        for graph in store.named_graphs() {
+            let graph = graph.map_err(|e| format!("Failed to list named graphs: {}", e))?;
+            graphs.push(node_to_string(&graph));
+        }
+
+        let mut info = json!({
+            "name": store_name,
+            "size": size,
+            "graphs": graphs,
+        });
+
+        // Run the aggregate query and copy its single solution into the result
+        if let QueryResults::Solutions(mut solutions) = run_query(store, STORE_INFO_QUERY, stores)? {
+            if let Some(solution) = solutions.next() {
+                let solution = solution.map_err(|e| format!("Error reading solution: {}", e))?;
+                for (var, term) in solution.iter() {
+                    info[var.as_str()] = format_term(term);
+                }
+            }
+        }
+
+        serde_json::to_string(&info)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Compute statistics about a store with the aggregate queries above: triple count,
+// distinct subjects, predicates and objects, distinct IRI and literal objects,
+// distinct blank node subjects, and the five most used predicates and types
+// (counting their distinct instances). Scans the store several times.
+#[wasm_func]
+pub fn store_statistics_query(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+
+        let mut statistics = serde_json::Map::new();
+        if let QueryResults::Solutions(mut solutions) = run_query(store, STORE_STATISTICS_QUERY, stores)? {
+            if let Some(solution) = solutions.next() {
+                let solution = solution.map_err(|e| format!("Error reading solution: {}", e))?;
+                for (var, term) in solution.iter() {
+                    statistics.insert(var.as_str().to_string(), format_term(term));
+                }
+            }
+        }
+
+        for (key, sparql) in [("top_predicates", TOP_PREDICATES_QUERY), ("top_types", TOP_TYPES_QUERY)] {
+            let rows = match run_query(store, sparql, stores)? {
+                QueryResults::Solutions(solutions) => solutions_to_rows(solutions, &prefixes, MAX_QUERY_ROWS)?.0,
+                _ => Vec::new(),
+            };
+            statistics.insert(key.to_string(), Value::Array(rows));
+        }
+
+        serde_json::to_string(&statistics)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Break the triples of a store down by the kind of their subject and object, in a
+// single pass over the quads without SPARQL. named_graphs counts the graphs that
+// hold at least one triple.
+#[wasm_func]
+pub fn get_store_size_by_type(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let (mut iri_subjects, mut blank_node_subjects) = (0u64, 0u64);
+        let (mut iri_objects, mut literal_objects, mut blank_node_objects, mut triple_objects) = (0u64, 0u64, 0u64, 0u64);
+        let mut named_graphs = BTreeSet::new();
+        for quad in store.iter() {
+            let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+            match quad.subject {
+                NamedOrBlankNode::NamedNode(_) => iri_subjects += 1,
+                NamedOrBlankNode::BlankNode(_) => blank_node_subjects += 1,
+            }
+            match quad.object {
+                Term::NamedNode(_) => iri_objects += 1,
+                Term::Literal(_) => literal_objects += 1,
+                Term::BlankNode(_) => blank_node_objects += 1,
+                Term::Triple(_) => triple_objects += 1,
+            }
+            if !quad.graph_name.is_default_graph() {
+                named_graphs.insert(quad.graph_name.to_string());
+            }
+        }
+
+        Ok(json!({
+            "iri_subjects": iri_subjects,
+            "blank_node_subjects": blank_node_subjects,
+            "iri_objects": iri_objects,
+            "literal_objects": literal_objects,
+            "blank_node_objects": blank_node_objects,
+            "triple_objects": triple_objects,
+            "named_graphs": named_graphs.len(),
+        })
+        .to_string())
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Breadth-first traversal of predicate edges starting from a node, equivalent to
+// the SPARQL property path `<start> <predicate>+ ?node` without writing SPARQL.
+// Returns the reachable nodes as a JSON array in the order they were discovered.
+#[wasm_func]
+pub fn find_reachable(store_name: &[u8], start_node_iri: &[u8], predicate_iri: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let start_iri = match String::from_utf8(start_node_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid start node IRI: {}", e).into_bytes(),
+    };
+
+    let predicate_iri = match String::from_utf8(predicate_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid predicate IRI: {}", e).into_bytes(),
     };
 
     match with_stores_mut(|stores| {
@@ -403,12 +1917,596 @@ pub fn get_store_size(store_name: &[u8]) -> Vec<u8> {
             .get(&store_name)
             .ok_or_else(|| format!("Store '{}' not found", store_name))?;
 
-        // Count quads in the store
-        let count = store.len().map_err(|e| format!("Failed to get store size: {}", e))?;
+        let start = NamedNode::new(start_iri)
+            .map_err(|e| format!("Invalid start node IRI: {}", e))?;
+        let predicate = NamedNode::new(predicate_iri)
+            .map_err(|e| format!("Invalid predicate IRI: {}", e))?;
 
-        Ok(count.to_string())
+        let mut visited = BTreeSet::new();
+        let mut reachable = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(NamedOrBlankNode::from(start));
+
+        while let Some(subject) = queue.pop_front() {
+            for quad in store.quads_for_pattern(Some(subject.as_ref()), Some(predicate.as_ref()), None, None) {
+                let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+                let node = match quad.object {
+                    Term::NamedNode(n) => NamedOrBlankNode::NamedNode(n),
+                    Term::BlankNode(b) => NamedOrBlankNode::BlankNode(b),
+                    // Literals and triple terms have no outgoing edges
+                    _ => continue,
+                };
+
+                if visited.insert(node.to_string()) {
+                    reachable.push(node_to_string(&node));
+                    queue.push_back(node);
+                }
+            }
+        }
+
+        serde_json::to_string(&reachable)
+            .map_err(|e| format!("JSON serialization error: {}", e))
     }) {
-        Ok(size_str) => size_str.into_bytes(),
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// List the distinct subjects of a store as a sorted JSON array. Blank nodes are
+// only included when include_blank_nodes is "true".
+#[wasm_func]
+pub fn get_subjects(store_name: &[u8], include_blank_nodes: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+    let include_blank_nodes = include_blank_nodes == b"true";
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+
+        let mut subjects = BTreeSet::new();
+        for quad in store.quads_for_pattern(None, None, None, None) {
+            let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+            if include_blank_nodes || quad.subject.is_named_node() {
+                subjects.insert(node_to_string(&quad.subject));
+            }
+        }
+
+        serde_json::to_string(&subjects)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// List the IRIs of all instances of a class (subjects of `rdf:type <type_iri>`)
+// as a sorted JSON array
+#[wasm_func]
+pub fn get_subjects_of_type(store_name: &[u8], type_iri: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let type_iri = match String::from_utf8(type_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid type IRI: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let class = NamedNode::new(type_iri).map_err(|e| format!("Invalid type IRI: {}", e))?;
+
+        let mut subjects = BTreeSet::new();
+        for quad in store.quads_for_pattern(None, Some(NamedNode::new_unchecked(RDF_TYPE).as_ref()), Some(class.as_ref().into()), None) {
+            let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+            if quad.subject.is_named_node() {
+                subjects.insert(node_to_string(&quad.subject));
+            }
+        }
+
+        serde_json::to_string(&subjects)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// List the distinct predicates used in a store as a sorted JSON array, shortened
+// with the store's registered prefixes like query results
+#[wasm_func]
+pub fn get_predicates(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+        collect_predicates(store, None, &prefixes)
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// List the distinct predicates used with a given subject as a sorted JSON array,
+// shortened with the store's registered prefixes
+#[wasm_func]
+pub fn get_predicates_for_subject(store_name: &[u8], subject_iri: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let subject_iri = match String::from_utf8(subject_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid subject IRI: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let subject = NamedNode::new(subject_iri).map_err(|e| format!("Invalid subject IRI: {}", e))?;
+        let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+        collect_predicates(store, Some(&subject), &prefixes)
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+fn collect_predicates(
+    store: &Store,
+    subject: Option<&NamedNode>,
+    prefixes: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let mut predicates = BTreeSet::new();
+    for quad in store.quads_for_pattern(subject.map(|s| s.as_ref().into()), None, None, None) {
+        let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+        if let Value::String(predicate) = format_term_with_prefixes(&quad.predicate.into(), prefixes) {
+            predicates.insert(predicate);
+        }
+    }
+
+    serde_json::to_string(&predicates)
+        .map_err(|e| format!("JSON serialization error: {}", e))
+}
+
+// Get all values of a property for an entity as a JSON array, with literals
+// converted the same way as in query results
+#[wasm_func]
+pub fn get_objects_for(store_name: &[u8], subject_iri: &[u8], predicate_iri: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let subject_iri = match String::from_utf8(subject_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid subject IRI: {}", e).into_bytes(),
+    };
+
+    let predicate_iri = match String::from_utf8(predicate_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid predicate IRI: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let subject = NamedNode::new(subject_iri).map_err(|e| format!("Invalid subject IRI: {}", e))?;
+        let predicate = NamedNode::new(predicate_iri)
+            .map_err(|e| format!("Invalid predicate IRI: {}", e))?;
+
+        let mut objects = Vec::new();
+        for quad in store.quads_for_pattern(Some(subject.as_ref().into()), Some(predicate.as_ref()), None, None) {
+            let quad = quad.map_err(|e| format!("Error reading quad: {}", e))?;
+            objects.push(format_term(&quad.object));
+        }
+
+        serde_json::to_string(&objects)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
         Err(e) => format!("ERROR: {}", e).into_bytes(),
     }
 }
+
+// Check whether a triple exists in any graph of a store without going through SPARQL.
+// The object is an IRI if written as `<iri>`, a blank node if written as `_:id`,
+// and a plain literal otherwise. Returns "true" or "false".
+#[wasm_func]
+pub fn triple_exists(store_name: &[u8], subject_iri: &[u8], predicate_iri: &[u8], object_encoded: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let subject_iri = match String::from_utf8(subject_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid subject IRI: {}", e).into_bytes(),
+    };
+
+    let predicate_iri = match String::from_utf8(predicate_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid predicate IRI: {}", e).into_bytes(),
+    };
+
+    let object_encoded = match String::from_utf8(object_encoded.to_vec()) {
+        Ok(object) => object,
+        Err(e) => return format!("ERROR: Invalid object: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let store = stores
+            .get(&store_name)
+            .ok_or_else(|| format!("Store '{}' not found", store_name))?;
+        let subject = NamedNode::new(subject_iri).map_err(|e| format!("Invalid subject IRI: {}", e))?;
+        let predicate = NamedNode::new(predicate_iri)
+            .map_err(|e| format!("Invalid predicate IRI: {}", e))?;
+        let object = parse_object(&object_encoded)?;
+
+        store
+            .quads_for_pattern(Some(subject.as_ref().into()), Some(predicate.as_ref()), Some(object.as_ref()), None)
+            .next()
+            .transpose()
+            .map(|quad| quad.is_some())
+            .map_err(|e| format!("Error reading quad: {}", e))
+    }) {
+        Ok(true) => b"true".to_vec(),
+        Ok(false) => b"false".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+fn parse_object(encoded: &str) -> Result<Term, String> {
+    if let Some(iri) = encoded.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+        NamedNode::new(iri)
+            .map(Term::from)
+            .map_err(|e| format!("Invalid object IRI: {}", e))
+    } else if let Some(id) = encoded.strip_prefix("_:") {
+        BlankNode::new(id)
+            .map(Term::from)
+            .map_err(|e| format!("Invalid object blank node: {}", e))
+    } else {
+        Ok(Literal::new_simple_literal(encoded).into())
+    }
+}
+
+// Register a prefix for a store; query then shortens matching IRIs to `prefix:local`
+#[wasm_func]
+pub fn add_prefix(store_name: &[u8], prefix: &[u8], namespace_iri: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let prefix = match String::from_utf8(prefix.to_vec()) {
+        Ok(prefix) => prefix,
+        Err(e) => return format!("ERROR: Invalid prefix: {}", e).into_bytes(),
+    };
+
+    let namespace_iri = match String::from_utf8(namespace_iri.to_vec()) {
+        Ok(iri) => iri,
+        Err(e) => return format!("ERROR: Invalid namespace IRI: {}", e).into_bytes(),
+    };
+
+    if let Err(e) = NamedNode::new(namespace_iri.as_str()) {
+        return format!("ERROR: Invalid namespace IRI: {}", e).into_bytes();
+    }
+
+    with_prefixes_mut(|registry| {
+        registry.entry(store_name).or_default().insert(prefix, namespace_iri);
+    });
+    b"OK".to_vec()
+}
+
+// Unregister a prefix previously added with add_prefix
+#[wasm_func]
+pub fn remove_prefix(store_name: &[u8], prefix: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let prefix = match String::from_utf8(prefix.to_vec()) {
+        Ok(prefix) => prefix,
+        Err(e) => return format!("ERROR: Invalid prefix: {}", e).into_bytes(),
+    };
+
+    let removed = with_prefixes_mut(|registry| {
+        registry
+            .get_mut(&store_name)
+            .and_then(|prefixes| prefixes.remove(&prefix))
+            .is_some()
+    });
+
+    if removed {
+        b"OK".to_vec()
+    } else {
+        format!("ERROR: Prefix '{}' is not registered for store '{}'", prefix, store_name).into_bytes()
+    }
+}
+
+// List the prefixes registered for a store as a JSON object of prefix to namespace
+#[wasm_func]
+pub fn list_prefixes(store_name: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let prefixes = with_prefixes_mut(|registry| registry.get(&store_name).cloned().unwrap_or_default());
+    match serde_json::to_string(&prefixes) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: JSON serialization error: {}", e).into_bytes(),
+    }
+}
+
+// Map a format name as passed from Typst to an RDF format
+#[cfg(feature = "network")]
+fn rdf_format_from_name(name: &str) -> Option<RdfFormat> {
+    match name {
+        "turtle" | "ttl" => Some(RdfFormat::Turtle),
+        "ntriples" | "nt" => Some(RdfFormat::NTriples),
+        "nquads" | "nq" => Some(RdfFormat::NQuads),
+        "trig" => Some(RdfFormat::TriG),
+        "n3" => Some(RdfFormat::N3),
+        "rdfxml" | "rdf" => Some(RdfFormat::RdfXml),
+        "jsonld" => Some(RdfFormat::JsonLd { profile: JsonLdProfileSet::empty() }),
+        _ => None,
+    }
+}
+
+// Download RDF data and load it into a store. The format is a name accepted by
+// rdf_format_from_name, or "auto" to use the response Content-Type (falling back
+// to the URL file extension). Only available with the `network` feature.
+#[cfg(feature = "network")]
+#[wasm_func]
+pub fn load_from_url(store_name: &[u8], url: &[u8], format: &[u8]) -> Vec<u8> {
+    let store_name = match store_name_arg(store_name) { Ok(name) => name, Err(e) => return e };
+
+    let url = match String::from_utf8(url.to_vec()) {
+        Ok(url) => url,
+        Err(e) => return format!("ERROR: Invalid URL: {}", e).into_bytes(),
+    };
+
+    let format_name = match String::from_utf8(format.to_vec()) {
+        Ok(format) => format,
+        Err(e) => return format!("ERROR: Invalid format: {}", e).into_bytes(),
+    };
+
+    let response = match ureq::get(&url).call() {
+        Ok(response) => response,
+        Err(e) => return format!("ERROR: Failed to fetch {}: {}", url, e).into_bytes(),
+    };
+
+    let format = if format_name == "auto" {
+        RdfFormat::from_media_type(response.content_type())
+            .or_else(|| url.rsplit('.').next().and_then(RdfFormat::from_extension))
+    } else {
+        rdf_format_from_name(&format_name)
+    };
+    let Some(format) = format else {
+        return format!("ERROR: Could not determine RDF format '{}' for {}", format_name, url).into_bytes();
+    };
+
+    let mut data = Vec::new();
+    if let Err(e) = std::io::Read::read_to_end(&mut response.into_reader(), &mut data) {
+        return format!("ERROR: Failed to read response from {}: {}", url, e).into_bytes();
+    }
+
+    match with_stores_mut(|stores| {
+        let store = get_or_create_store(stores, &store_name)?;
+        store
+            .load_from_reader(format, data.as_slice())
+            .map_err(|e| format!("Failed to parse data from {}: {}", url, e))?;
+        Ok(())
+    }) {
+        Ok(_) => b"OK".to_vec(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+// Query the union of several stores. The primary store and every store named in
+// extra_stores_json (a JSON array of store names) are merged into a temporary
+// store that the query runs against; the original stores are left untouched.
+#[wasm_func]
+pub fn query_federated(primary_store: &[u8], sparql_query: &[u8], extra_stores_json: &[u8]) -> Vec<u8> {
+    let primary_name = match store_name_arg(primary_store) { Ok(name) => name, Err(e) => return e };
+
+    let sparql = match String::from_utf8(sparql_query.to_vec()) {
+        Ok(query) => query,
+        Err(e) => return format!("ERROR: Invalid SPARQL query: {}", e).into_bytes(),
+    };
+
+    let extra_names: Vec<String> = match serde_json::from_slice::<Vec<String>>(extra_stores_json) {
+        Ok(names) => names.iter().map(|name| normalize_store_name(name)).collect(),
+        Err(e) => return format!("ERROR: Extra stores must be a JSON array of store names: {}", e).into_bytes(),
+    };
+
+    match with_stores_mut(|stores| {
+        let federated = Store::new().map_err(|e| format!("Failed to create store: {}", e))?;
+        for name in core::iter::once(&primary_name).chain(extra_names.iter()) {
+            let store = stores
+                .get(name)
+                .ok_or_else(|| format!("Store '{}' not found", name))?;
+            merge_quads(store, &federated)?;
+        }
+
+        let results = run_query(&federated, &sparql, stores)?;
+
+        // Prefixes registered for the primary store are used to shorten IRIs
+        let prefixes = with_prefixes_mut(|registry| registry.get(&primary_name).cloned().unwrap_or_default());
+        results_to_json(results, &prefixes)
+    }) {
+        Ok(json_result) => json_result.into_bytes(),
+        Err(e) => format!("ERROR: {}", e).into_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIPLE: &[u8] = b"<http://example.org/a> <http://example.org/p> \"x\" .";
+
+    fn text(result: Vec<u8>) -> String {
+        String::from_utf8(result).unwrap()
+    }
+
+    // The plugin state lives in `static mut`s that Typst only uses from one thread,
+    // while cargo runs tests in parallel: every test holds this lock
+    static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn lock() -> std::sync::MutexGuard<'static, ()> {
+        TEST_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[test]
+    fn load_turtle_rejects_invalid_utf8_store_name() {
+        let _lock = lock();
+        assert!(text(load_turtle(&[0xff, 0xfe], TRIPLE)).starts_with("ERROR: Invalid store name:"));
+    }
+
+    #[test]
+    fn load_turtle_rejects_invalid_utf8_data() {
+        let _lock = lock();
+        let data = b"<http://example.org/a> <http://example.org/p> \"\xff\" .";
+        let result = text(load_turtle(b"invalid-utf8-data", data));
+        assert!(result.starts_with("ERROR: Failed to parse Turtle data:"), "{}", result);
+        assert!(result.contains("Invalid UTF-8"), "{}", result);
+        assert_eq!(text(get_store_size(b"invalid-utf8-data")), "0");
+    }
+
+    #[test]
+    fn load_turtle_accepts_empty_store_name() {
+        let _lock = lock();
+        assert_eq!(text(load_turtle(b"", TRIPLE)), "OK");
+        assert_eq!(text(get_store_size(b"  ")), "1");
+    }
+
+    #[test]
+    fn load_turtle_lowercases_unicode_store_names() {
+        let _lock = lock();
+        assert_eq!(text(load_turtle("Größe-Ünïcode".as_bytes(), TRIPLE)), "OK");
+        assert_eq!(text(get_store_size("GRÖßE-üNÏCODE".as_bytes())), "1");
+        assert!(text(get_store_size("GRÖSSE-ÜNÏCODE".as_bytes())).starts_with("ERROR:"));
+    }
+
+    #[test]
+    fn store_names_are_matched_case_insensitively() {
+        let _lock = lock();
+        assert_eq!(text(load_turtle(b"MyStore", TRIPLE)), "OK");
+        let results = text(query(b" mystore ", b"SELECT ?o WHERE { ?s ?p ?o }"));
+        assert_eq!(results, r#"[{"o":"x"}]"#);
+    }
+
+    #[test]
+    fn load_jsonld_hints_at_remote_contexts_only_when_used() {
+        let _lock = lock();
+        let remote = br#"{"@context": "https://schema.org/", "@id": "http://example.org/a", "name": "A"}"#;
+        let result = text(load_jsonld(b"jsonld-errors", remote));
+        assert!(result.contains("(remote contexts are not supported)"), "{}", result);
+
+        let result = text(load_jsonld(b"jsonld-errors", br#"{"@id": "http://example.org/a",}"#));
+        assert!(result.starts_with("ERROR: Failed to parse JSON-LD data: "), "{}", result);
+    }
+
+    #[test]
+    fn create_store_with_options_refuses_existing_stores() {
+        let _lock = lock();
+        let options = br#"{"initial_capacity_hint": 10000}"#;
+        assert_eq!(text(create_store_with_options(b"Preallocated", options)), "OK");
+        assert_eq!(text(get_store_size(b"preallocated")), "0");
+        assert_eq!(
+            text(create_store_with_options(b"preallocated", b"{}")),
+            "ERROR: Store 'preallocated' already exists"
+        );
+        assert_eq!(
+            text(create_store_with_options(b"other", br#"{"capacity": 1}"#)),
+            "ERROR: Unknown store option 'capacity'"
+        );
+    }
+
+    #[test]
+    fn store_statistics_query_counts_terms_and_ranks_types() {
+        let _lock = lock();
+        let data = br#"@prefix ex: <http://example.org/> .
+            ex:alice a ex:Person ; ex:knows ex:bob , _:carol ; ex:name "Alice" .
+            ex:bob a ex:Person ; ex:name "Bob" .
+            _:carol a ex:Robot ; ex:name "Alice" ."#;
+        assert_eq!(text(load_turtle(b"statistics", data)), "OK");
+        let statistics: Value = serde_json::from_slice(&store_statistics_query(b"statistics")).unwrap();
+        assert_eq!(
+            statistics,
+            json!({
+                "triples": 8, "subjects": 3, "predicates": 3, "objects": 6,
+                "iri_objects": 3, "literal_objects": 2, "blank_node_subjects": 1,
+                "top_predicates": [
+                    { "predicate": "http://example.org/name", "count": 3 },
+                    { "predicate": "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "count": 3 },
+                    { "predicate": "http://example.org/knows", "count": 2 },
+                ],
+                "top_types": [
+                    { "type": "http://example.org/Person", "count": 2 },
+                    { "type": "http://example.org/Robot", "count": 1 },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn query_keeps_strings_and_booleans() {
+        let _lock = lock();
+        let data = br#"<http://example.org/a> <http://example.org/code> "00123" ;
+            <http://example.org/flag> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> ."#;
+        assert_eq!(text(load_turtle(b"literal-types", data)), "OK");
+        let sparql = b"SELECT ?code ?flag WHERE { ?s <http://example.org/code> ?code ; <http://example.org/flag> ?flag }";
+        let results: Value = serde_json::from_slice(&query(b"literal-types", sparql)).unwrap();
+        assert_eq!(results, json!([{ "code": "00123", "flag": true }]));
+    }
+
+    #[test]
+    fn query_to_typst_dict_writes_typst_literals() {
+        let _lock = lock();
+        let data = br#"<http://example.org/a> <http://example.org/p> "say \"hi\"\n" ;
+            <http://example.org/n> 42 ; <http://example.org/d> 1.5e0 ."#;
+        assert_eq!(text(load_turtle(b"typst-dict", data)), "OK");
+        let sparql = b"SELECT ?text ?n ?d ?missing WHERE { ?s <http://example.org/p> ?text ; \
+            <http://example.org/n> ?n ; <http://example.org/d> ?d OPTIONAL { ?s <http://example.org/x> ?missing } }";
+        assert_eq!(
+            text(query_to_typst_dict(b"typst-dict", sparql)),
+            r#"(("d": 1.5, "n": 42, "text": "say \"hi\"\n"), )"#
+        );
+    }
+
+    #[test]
+    fn load_sessions_keep_blank_nodes_apart() {
+        let _lock = lock();
+        for value in ["1", "2"] {
+            let session = load_turtle_begin(b"sessions");
+            let data = format!("_:b <http://example.org/p> \"{}\" .", value);
+            let (head, tail) = data.split_at(5);
+            assert_eq!(text(load_turtle_chunk(&session, head.as_bytes())), "OK");
+            assert_eq!(text(load_turtle_chunk(&session, tail.as_bytes())), "OK");
+            assert_eq!(text(load_turtle_end(&session)), "OK: 1");
+        }
+        assert_eq!(text(load_turtle(b"sessions", b"_:b <http://example.org/p> \"3\" .")), "OK");
+        let results: Value =
+            serde_json::from_slice(&query(b"sessions", b"SELECT (COUNT(DISTINCT ?s) AS ?n) WHERE { ?s ?p ?o }")).unwrap();
+        assert_eq!(results, json!([{ "n": 3 }]));
+    }
+
+    #[test]
+    fn get_predicates_uses_registered_prefixes() {
+        let _lock = lock();
+        let data = br#"<http://example.org/a> <http://example.org/name> "A" ;
+            <http://xmlns.com/foaf/0.1/knows> <http://example.org/b> ."#;
+        assert_eq!(text(load_turtle(b"predicates", data)), "OK");
+        assert_eq!(
+            text(get_predicates(b"predicates")),
+            r#"["http://example.org/name","http://xmlns.com/foaf/0.1/knows"]"#
+        );
+        assert_eq!(text(add_prefix(b"predicates", b"ex", b"http://example.org/")), "OK");
+        assert_eq!(text(get_predicates(b"predicates")), r#"["ex:name","http://xmlns.com/foaf/0.1/knows"]"#);
+        assert_eq!(
+            text(get_predicates_for_subject(b"predicates", b"http://example.org/a")),
+            r#"["ex:name","http://xmlns.com/foaf/0.1/knows"]"#
+        );
+    }
+
+    #[test]
+    fn load_turtle_keeps_special_characters_in_store_names() {
+        let _lock = lock();
+        assert_eq!(text(load_turtle(b"data/people:v1", TRIPLE)), "OK");
+        assert_eq!(text(get_store_size(b"data/people:v1")), "1");
+        assert!(text(get_store_size(b"data/people")).starts_with("ERROR:"));
+    }
+}
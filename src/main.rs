@@ -21,42 +21,700 @@
  */
 
 use anyhow::{Context, Result};
-use clap::{Arg, Command};
-use oxigraph::io::RdfFormat;
+use clap::{Arg, Command, ValueEnum, ValueHint};
+use clap_complete::Shell;
+use oxigraph::io::{RdfFormat, RdfParser, RdfSerializer};
 use oxigraph::model::*;
+use oxigraph::sparql::results::{QueryResultsFormat, QueryResultsParser, QueryResultsSerializer};
+use oxigraph::sparql::{
+    CancellationToken, PreparedSparqlQuery, PreparedSparqlUpdate, QueryResults, QuerySolutionIter,
+    QueryTripleIter, SparqlEvaluator,
+};
 use oxigraph::store::Store;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use owo_colors::OwoColorize;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+use typox_core::namespaces::{COMMON_PREFIXES, RDF_LANG_STRING, RDF_TYPE, VOID_DATASET, VOID_NS};
+use typox_core::{TypedLiteral, API_VERSION, XSD_STRING};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
-const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
-
-// XSD datatype constants
-const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
-const XSD_INT: &str = "http://www.w3.org/2001/XMLSchema#int";
-const XSD_LONG: &str = "http://www.w3.org/2001/XMLSchema#long";
-const XSD_SHORT: &str = "http://www.w3.org/2001/XMLSchema#short";
-const XSD_BYTE: &str = "http://www.w3.org/2001/XMLSchema#byte";
-const XSD_NON_NEGATIVE_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#nonNegativeInteger";
-const XSD_POSITIVE_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#positiveInteger";
-const XSD_UNSIGNED_INT: &str = "http://www.w3.org/2001/XMLSchema#unsignedInt";
-const XSD_UNSIGNED_LONG: &str = "http://www.w3.org/2001/XMLSchema#unsignedLong";
-const XSD_UNSIGNED_SHORT: &str = "http://www.w3.org/2001/XMLSchema#unsignedShort";
-const XSD_UNSIGNED_BYTE: &str = "http://www.w3.org/2001/XMLSchema#unsignedByte";
-const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
-const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
-const XSD_FLOAT: &str = "http://www.w3.org/2001/XMLSchema#float";
+
+// `memory://NAME` stores are empty in-memory stores that are discarded when the
+// command exits
+const MEMORY_STORE_SCHEME: &str = "memory://";
+
+// Named graph holding the description written by `load --generate-void`
+const VOID_GRAPH: &str = VOID_DATASET;
 
 enum DataSource {
     LocalStore(Store),
     HttpEndpoint(String),
 }
 
+// A file matched by a `--files` argument. Downloaded files are kept in a
+// temporary file that is deleted once the source is dropped, and files
+// extracted from an archive share a temporary directory that is removed once
+// the last of them is dropped. Manifest entries that name their format or graph
+// wrap each source they resolve to in FromManifest, so that a file listed twice
+// keeps the graph of each entry.
+enum SourceFile {
+    Local(PathBuf),
+    Downloaded { url: String, file: tempfile::NamedTempFile },
+    Extracted { archive: String, entry: PathBuf, dir: Rc<tempfile::TempDir> },
+    FromManifest { source: Box<SourceFile>, format: Option<RdfFormat>, graph: Option<NamedNode> },
+}
+
+impl SourceFile {
+    fn path(&self) -> PathBuf {
+        match self {
+            SourceFile::Local(path) => path.clone(),
+            SourceFile::Downloaded { file, .. } => file.path().to_path_buf(),
+            SourceFile::Extracted { entry, dir, .. } => dir.path().join(entry),
+            SourceFile::FromManifest { source, .. } => source.path(),
+        }
+    }
+
+    // Picks the parser from the file name, falling back to Turtle for unknown
+    // extensions as `typox load` has always done
+    fn format(&self) -> RdfFormat {
+        match self {
+            SourceFile::FromManifest { format: Some(format), .. } => *format,
+            _ => match detect_format(&self.name()) {
+                Some(SourceFormat::Rdf(format)) => format,
+                _ => RdfFormat::Turtle,
+            },
+        }
+    }
+
+    // `.ru` files hold SPARQL UPDATE scripts that are executed instead of parsed.
+    // Only local files named explicitly are: scripts matched by a glob, found in
+    // an archive or downloaded are skipped, see skip_sparql_update.
+    fn is_sparql_update(&self) -> bool {
+        match self {
+            SourceFile::Local(path) => detect_format(path) == Some(SourceFormat::SparqlUpdate),
+            SourceFile::FromManifest { source, format: None, .. } => source.is_sparql_update(),
+            _ => false,
+        }
+    }
+
+    // Reads the whole file, decompressing `.gz` files on the fly
+    fn read(&self) -> Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut content = Vec::new();
+        self.decompress(self.open()?)
+            .read_to_end(&mut content)
+            .with_context(|| {
+                if is_gzip(&self.name()) {
+                    format!("Failed to decompress gzip file: {}", self)
+                } else {
+                    format!("Failed to read file: {}", self)
+                }
+            })?;
+        Ok(content)
+    }
+
+    // The file as stored, for reading it as a stream. Named pipes (FIFOs) can only
+    // be read once and are never held in memory as a whole this way.
+    fn open(&self) -> Result<fs::File> {
+        fs::File::open(self.path()).with_context(|| format!("Failed to read file: {}", self))
+    }
+
+    fn decompress<'a>(&self, reader: impl std::io::Read + 'a) -> Box<dyn std::io::Read + 'a> {
+        if is_gzip(&self.name()) {
+            Box::new(flate2::read::MultiGzDecoder::new(reader))
+        } else {
+            Box::new(reader)
+        }
+    }
+
+    fn name(&self) -> PathBuf {
+        match self {
+            SourceFile::Local(path) => path.clone(),
+            SourceFile::Downloaded { url, .. } => PathBuf::from(url),
+            SourceFile::Extracted { entry, .. } => entry.clone(),
+            SourceFile::FromManifest { source, .. } => source.name(),
+        }
+    }
+
+    // The IRI identifying where the file came from: a file:// IRI for local files,
+    // the URL for downloads, and the archive's IRI followed by the entry path for
+    // files extracted from an archive
+    fn iri(&self) -> Result<url::Url> {
+        match self {
+            SourceFile::Local(path) => file_iri(path),
+            SourceFile::Downloaded { url, .. } => {
+                url::Url::parse(url).with_context(|| format!("Invalid URL: {}", url))
+            }
+            SourceFile::Extracted { archive, entry, .. } => {
+                let mut iri = if archive.starts_with("http://") || archive.starts_with("https://") {
+                    url::Url::parse(archive).with_context(|| format!("Invalid URL: {}", archive))?
+                } else {
+                    file_iri(Path::new(archive))?
+                };
+                iri.path_segments_mut()
+                    .map_err(|()| anyhow::anyhow!("Cannot derive an IRI from: {}", archive))?
+                    .extend(entry.iter().map(|segment| segment.to_string_lossy()));
+                Ok(iri)
+            }
+            SourceFile::FromManifest { source, .. } => source.iri(),
+        }
+    }
+
+    // Identifies the source in graph mappings: the canonical path for local files,
+    // the URL or archive entry name otherwise
+    fn mapping_key(&self) -> Result<String> {
+        match self {
+            SourceFile::Local(path) => Ok(fs::canonicalize(path)?.display().to_string()),
+            SourceFile::FromManifest { source, .. } => source.mapping_key(),
+            _ => Ok(self.to_string()),
+        }
+    }
+}
+
+fn file_iri(path: &Path) -> Result<url::Url> {
+    let path = fs::canonicalize(path)
+        .with_context(|| format!("Failed to resolve path: {}", path.display()))?;
+    url::Url::from_file_path(&path)
+        .map_err(|()| anyhow::anyhow!("Cannot derive an IRI from: {}", path.display()))
+}
+
+// Which named graph `typox load` puts the triples of each file into
+enum GraphAssignment {
+    // Triples go to the default graph, quads keep their graph
+    Default,
+    // Each file gets the graph named after its IRI
+    PerFile,
+    // Files listed in a --graph-mapping file get the graph given there, keyed by
+    // canonical path for local files and by URL or archive entry name otherwise
+    Mapping(HashMap<String, NamedNode>),
+}
+
+impl GraphAssignment {
+    fn read_mapping(mapping_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(mapping_path)
+            .with_context(|| format!("Failed to read graph mapping: {}", mapping_path.display()))?;
+        let entries: HashMap<String, String> = serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Graph mapping must be a JSON object of file paths to graph IRIs: {}",
+                mapping_path.display()
+            )
+        })?;
+
+        let mut mapping = HashMap::new();
+        for (file, graph) in entries {
+            let graph = NamedNode::new(&graph)
+                .with_context(|| format!("Invalid graph IRI for {}: {}", file, graph))?;
+            // Relative paths are resolved against the current directory, like --files
+            let key = match fs::canonicalize(&file) {
+                Ok(path) => path.display().to_string(),
+                Err(_) => file,
+            };
+            mapping.insert(key, graph);
+        }
+        Ok(GraphAssignment::Mapping(mapping))
+    }
+
+    // The graph named by a source's manifest entry wins over the assignment
+    fn graph_for(&self, source: &SourceFile) -> Result<Option<NamedNode>> {
+        if let SourceFile::FromManifest { graph: Some(graph), .. } = source {
+            return Ok(Some(graph.clone()));
+        }
+        match self {
+            GraphAssignment::Default => Ok(None),
+            GraphAssignment::PerFile => Ok(Some(NamedNode::new(source.iri()?.as_str())?)),
+            GraphAssignment::Mapping(mapping) => Ok(mapping.get(&source.mapping_key()?).cloned()),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceFile::Local(path) => write!(f, "{}", path.display()),
+            SourceFile::Downloaded { url, .. } => write!(f, "{}", url),
+            SourceFile::Extracted { archive, entry, .. } => {
+                write!(f, "{}/{}", archive, entry.display())
+            }
+            SourceFile::FromManifest { source, .. } => source.fmt(f),
+        }
+    }
+}
+
+// One instruction of a `typox load --manifest` file. Exactly one of `file` (a
+// path or glob, relative to the manifest) and `url` is given; `format` overrides
+// the format detected from the file name and `graph` names the target graph.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    file: Option<String>,
+    url: Option<String>,
+    format: Option<String>,
+    graph: Option<String>,
+}
+
+// Resolves the manifest entries, in order, to the sources they name and the graph
+// assignment of those listed with a graph
+async fn read_manifest(manifest_path: &Path, options: SourceOptions) -> Result<Vec<SourceFile>> {
+    let content = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+    let entries: Vec<ManifestEntry> = serde_json::from_str(&content).with_context(|| {
+        format!(
+            "Manifest must be a JSON array of {{\"file\"|\"url\", \"format\", \"graph\"}} objects: {}",
+            manifest_path.display()
+        )
+    })?;
+    let base_dir = manifest_path.parent().unwrap_or(Path::new(""));
+
+    let mut sources = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let pattern = match (entry.file, entry.url) {
+            (Some(file), None) => base_dir.join(file).display().to_string(),
+            (None, Some(url)) if url.starts_with("http://") || url.starts_with("https://") => url,
+            (None, Some(url)) => anyhow::bail!("Manifest entry {}: not an HTTP(S) URL: {}", index + 1, url),
+            _ => anyhow::bail!("Manifest entry {} needs exactly one of \"file\" and \"url\"", index + 1),
+        };
+        let format = entry
+            .format
+            .map(|name| {
+                parse_format_name(&name)
+                    .with_context(|| format!("Manifest entry {}: unknown format: {}", index + 1, name))
+            })
+            .transpose()?;
+        let graph = entry
+            .graph
+            .map(|graph| {
+                NamedNode::new(&graph)
+                    .with_context(|| format!("Manifest entry {}: invalid graph IRI: {}", index + 1, graph))
+            })
+            .transpose()?;
+
+        for source in expand_sources(&pattern, options).await? {
+            sources.push(match (format, &graph) {
+                (None, None) => source,
+                _ => SourceFile::FromManifest { source: Box::new(source), format, graph: graph.clone() },
+            });
+        }
+    }
+    Ok(sources)
+}
+
+// Format names accepted in manifests, besides file extensions such as "ttl"
+fn parse_format_name(name: &str) -> Option<RdfFormat> {
+    match name.to_ascii_lowercase().replace(['-', '/', '_'], "").as_str() {
+        "turtle" => Some(RdfFormat::Turtle),
+        "ntriples" => Some(RdfFormat::NTriples),
+        "nquads" => Some(RdfFormat::NQuads),
+        "trig" => Some(RdfFormat::TriG),
+        "rdfxml" => Some(RdfFormat::RdfXml),
+        "n3" => Some(RdfFormat::N3),
+        "jsonld" => RdfFormat::from_extension("jsonld"),
+        _ => RdfFormat::from_extension(name),
+    }
+}
+
+// Whether `typox load` reports triples that are already in the store
+#[derive(Clone, Copy, PartialEq)]
+enum DuplicateCheck {
+    Off,
+    Warn,
+    Count,
+}
+
+// How `typox load` writes the parsed sources into the store
+struct LoadOptions {
+    duplicates: DuplicateCheck,
+    graphs: GraphAssignment,
+    // Resolves relative IRIs of the data files
+    base_iri: Option<String>,
+    report_overlap: bool,
+    progress: bool,
+}
+
+impl LoadOptions {
+    // The parser of a data source, targeting its graph
+    fn parser_for(&self, source: &SourceFile) -> Result<RdfParser> {
+        source_parser(source, self.graphs.graph_for(source)?, self.base_iri.as_deref())
+    }
+}
+
+// How `--files` arguments are turned into source files
+#[derive(Clone, Copy)]
+struct SourceOptions {
+    download_timeout: Duration,
+    unzip: bool,
+}
+
+// How a SPARQL query is sent to an HTTP endpoint
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum EndpointFormat {
+    /// POST with a form-encoded `query=` body (SPARQL 1.1 Protocol default)
+    PostForm,
+    /// POST with the raw query as an `application/sparql-query` body
+    PostBody,
+    /// GET with the query in the `?query=` URL parameter
+    Get,
+}
+
+// Which HTTP API the store URL speaks
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Protocol {
+    /// Send the query to a SPARQL 1.1 Protocol endpoint
+    Sparql,
+    /// Download the whole dataset through the Graph Store Protocol (e.g. Oxigraph's
+    /// `/store`) and evaluate the query locally in memory
+    Gsp,
+}
+
+// How query results are written
+#[derive(Clone, Copy, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    /// A single pretty-printed JSON document
+    #[default]
+    Json,
+    /// One JSON object per line, streamed without buffering the whole result set
+    Ndjson,
+    /// An aligned text table for reading results in a terminal
+    Table,
+    /// W3C SPARQL 1.1 Query Results JSON, for other SPARQL tools
+    #[serde(rename = "sparql-json")]
+    SparqlJson,
+    /// A Graphviz digraph: CONSTRUCT triples, or SELECT rows binding ?source,
+    /// ?label and ?target, become edges
+    Dot,
+    /// A LaTeX tabular environment, numbers in siunitx's \num{}
+    Latex,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Table => "txt",
+            OutputFormat::SparqlJson => "srj",
+            OutputFormat::Dot => "dot",
+            OutputFormat::Latex => "tex",
+        }
+    }
+}
+
+// The output format, and whether table output may use ANSI colors. Machine
+// readable formats never contain colors.
+#[derive(Clone, Copy)]
+struct OutputOptions {
+    format: OutputFormat,
+    color: bool,
+    distinct: bool,
+    include_schema: bool,
+}
+
+impl OutputOptions {
+    fn from_matches(matches: &clap::ArgMatches) -> Self {
+        let writes_to_terminal =
+            matches.get_one::<String>("output").is_none() && std::io::stdout().is_terminal();
+
+        // Explicit flags win over NO_COLOR (https://no-color.org), which wins over
+        // auto-detection
+        let color = if matches.get_flag("no-color") {
+            false
+        } else if matches.get_flag("color") {
+            true
+        } else if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            false
+        } else {
+            writes_to_terminal && std::env::var("TERM").map_or(true, |term| term != "dumb")
+        };
+
+        OutputOptions {
+            format: *matches.get_one::<OutputFormat>("format").unwrap(),
+            color,
+            distinct: matches.get_flag("distinct"),
+            include_schema: matches.get_flag("include-schema"),
+        }
+    }
+}
+
+// Defaults read from config.toml in the user's configuration directory
+// (~/.config/typox/ on Linux). Command-line arguments and TYPOX_STORE take
+// precedence over every setting.
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserConfig {
+    default_store: Option<String>,
+    default_format: Option<OutputFormat>,
+    // Seconds, for HTTP endpoint requests made without --timeout
+    http_timeout: Option<u64>,
+    #[serde(default)]
+    http_retries: u32,
+    // Namespaces used to shorten IRIs, on top of the built-in ones; the query's own
+    // PREFIX declarations still win
+    #[serde(default)]
+    prefixes: HashMap<String, String>,
+}
+
+impl UserConfig {
+    fn path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "typox").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    // A missing file means no defaults, an invalid one is an error
+    fn read() -> Result<Self> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(UserConfig::default());
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config file: {}", path.display()))
+    }
+
+    // --store, then TYPOX_STORE, then default_store
+    fn store_param(&self, matches: &clap::ArgMatches) -> Option<String> {
+        matches
+            .get_one::<String>("store")
+            .cloned()
+            .or_else(|| std::env::var("TYPOX_STORE").ok())
+            .or_else(|| self.default_store.clone())
+    }
+}
+
+// A `typox batch` configuration file, in YAML or TOML
+#[derive(serde::Deserialize)]
+struct BatchConfig {
+    queries: Vec<BatchQuery>,
+}
+
+#[derive(serde::Deserialize)]
+struct BatchQuery {
+    name: String,
+    query: String,
+    #[serde(default)]
+    format: OutputFormat,
+    // Relative to the output directory, defaults to `<name>.<format>`
+    output: Option<String>,
+}
+
+impl BatchQuery {
+    fn output_file(&self) -> String {
+        self.output.clone().unwrap_or_else(|| format!("{}.{}", self.name, self.format.extension()))
+    }
+}
+
+// Options that only apply when querying HTTP endpoints. Responses are cached on
+// disk so that repeated Typst compilation passes don't re-issue identical requests.
+struct HttpOptions {
+    protocol: Protocol,
+    endpoint_format: EndpointFormat,
+    cache_enabled: bool,
+    cache_ttl: Duration,
+    bearer_token: Option<String>,
+    // Extra attempts after a connection error or a 5xx response
+    retries: u32,
+    // Treat the store URL as a site's base URL and look for its SPARQL endpoint
+    discover: bool,
+}
+
+// How long a query may run. HTTP requests get a client timeout; local evaluation
+// stops with an error once the token is cancelled by the --timeout watchdog.
+struct QueryTimeout {
+    http: Option<Duration>,
+    cancellation: CancellationToken,
+}
+
+impl HttpOptions {
+    fn from_matches(matches: &clap::ArgMatches) -> Self {
+        Self {
+            protocol: *matches.get_one::<Protocol>("protocol").unwrap(),
+            endpoint_format: *matches.get_one::<EndpointFormat>("sparql-endpoint-format").unwrap(),
+            cache_enabled: !matches.get_flag("no-cache"),
+            cache_ttl: Duration::from_secs(*matches.get_one::<u64>("cache-ttl").unwrap()),
+            bearer_token: std::env::var("TYPOX_ENDPOINT_BEARER_TOKEN").ok(),
+            retries: 0,
+            discover: matches.get_flag("discover"),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let matches = Command::new("typox")
+    let matches = build_cli().get_matches();
+
+    match matches.subcommand() {
+        Some(("query", query_matches)) => {
+            let mut query = match query_matches.get_one::<String>("query-file") {
+                Some(query_file) => fs::read_to_string(query_file)
+                    .with_context(|| format!("Failed to read query file: {}", query_file))?,
+                None => query_matches.get_one::<String>("query").unwrap().clone(),
+            };
+            if query_matches.get_flag("strip-comments") {
+                query = strip_comments(&query);
+            }
+
+            let explain = query_matches.get_flag("explain");
+
+            let template = query_matches.get_flag("query-template");
+
+            // Only query files can hold a script of several statements
+            let script = query_matches.contains_id("query-file");
+
+            run_query(query_matches, &query, explain, template, script).await?;
+        }
+        Some(("load", load_matches)) => {
+            let store_path = load_matches.get_one::<String>("store").unwrap();
+            let files: Vec<&String> = load_matches.get_many::<String>("files").unwrap_or_default().collect();
+            let create_new = load_matches.get_flag("create");
+            // Checked before any source is downloaded or parsed, so that a mistyped
+            // store path fails fast instead of leaving a new store behind
+            if load_matches.get_flag("no-create")
+                && !store_path.starts_with(MEMORY_STORE_SCHEME)
+                && !Path::new(store_path).exists()
+            {
+                anyhow::bail!("Store path does not exist: {} (--no-create was given)", store_path);
+            }
+            let source_options = SourceOptions {
+                download_timeout: Duration::from_secs(
+                    *load_matches.get_one::<u64>("download-timeout").unwrap(),
+                ),
+                unzip: load_matches.get_flag("unzip"),
+            };
+            let duplicates = if load_matches.get_flag("warn-duplicates") {
+                DuplicateCheck::Warn
+            } else if load_matches.get_flag("count-duplicates") {
+                DuplicateCheck::Count
+            } else {
+                DuplicateCheck::Off
+            };
+            let (mut sources, graphs) = match load_matches.get_one::<String>("manifest") {
+                Some(manifest_path) => {
+                    (read_manifest(Path::new(manifest_path), source_options).await?, GraphAssignment::Default)
+                }
+                None => match load_matches.get_one::<String>("graph-mapping") {
+                    Some(mapping_path) => (Vec::new(), GraphAssignment::read_mapping(Path::new(mapping_path))?),
+                    None if load_matches.get_flag("graph-per-file") => (Vec::new(), GraphAssignment::PerFile),
+                    None => (Vec::new(), GraphAssignment::Default),
+                },
+            };
+
+            for file_pattern in files {
+                sources.extend(expand_sources(file_pattern, source_options).await?);
+            }
+            if let Some(endpoint_url) = load_matches.get_one::<String>("from-endpoint") {
+                let construct = load_matches.get_one::<String>("construct").unwrap();
+                sources.push(
+                    construct_source_file(endpoint_url, construct, source_options.download_timeout)
+                        .await?,
+                );
+            }
+
+            // These options read every source a second time, which would drain a
+            // named pipe before it is loaded or after it has been
+            let rereading = ["verify", "validate-iris", "report-overlap", "deduplicate-files"]
+                .into_iter()
+                .find(|flag| load_matches.get_flag(flag));
+            if let (Some(flag), Some(pipe)) = (rereading, sources.iter().find(|source| !source.path().is_file())) {
+                anyhow::bail!(
+                    "--{} reads the files twice and cannot be used with {}, which is not a regular file",
+                    flag,
+                    pipe.path().display()
+                );
+            }
+
+            if load_matches.get_flag("deduplicate-files") {
+                sources = deduplicate_sources(sources, &graphs)?;
+            }
+
+            let load_options = LoadOptions {
+                duplicates,
+                graphs,
+                base_iri: load_matches.get_one::<String>("base-iri").cloned(),
+                report_overlap: load_matches.get_flag("report-overlap"),
+                progress: load_matches.get_flag("progress"),
+            };
+
+            if load_matches.get_flag("validate-iris") {
+                validate_iris(&sources, &load_options)?;
+            }
+
+            if load_matches.get_flag("dry-run") {
+                dry_run_turtle_files(&sources, load_options.base_iri.as_deref())?;
+            } else {
+                let store = load_turtle_files(store_path, &sources, create_new, &load_options)?;
+                if load_matches.get_flag("verify") {
+                    verify_loaded_triples(&store, &sources, &load_options)?;
+                }
+                if load_matches.get_flag("generate-void") {
+                    write_void_description(&store, store_path)?;
+                }
+            }
+        }
+        Some(("batch", batch_matches)) => {
+            run_batch(batch_matches).await?;
+        }
+        Some(("compact", compact_matches)) => {
+            let store_path = compact_matches.get_one::<String>("store").unwrap();
+
+            compact_store(store_path)?;
+        }
+        Some(("merge", merge_matches)) => {
+            let output_path = merge_matches.get_one::<String>("output").unwrap();
+            let inputs: Vec<&String> = merge_matches.get_many::<String>("input").unwrap().collect();
+
+            merge_stores(output_path, &inputs)?;
+        }
+        Some(("clear-graph", clear_matches)) => {
+            let store_path = clear_matches.get_one::<String>("store").unwrap();
+            let graph = clear_matches.get_one::<String>("graph").unwrap();
+
+            clear_graph(store_path, graph)?;
+        }
+        Some(("info", _)) => {
+            print_build_info();
+        }
+        Some(("completions", completions_matches)) => {
+            let shell = completions_matches
+                .get_one::<Shell>("shell")
+                .or_else(|| completions_matches.get_one::<Shell>("shell-option"))
+                .unwrap();
+
+            clap_complete::generate(*shell, &mut build_cli(), "typox", &mut std::io::stdout());
+        }
+        Some(("man", man_matches)) => {
+            match man_matches.get_one::<String>("output-dir") {
+                Some(output_dir) => write_man_pages(Path::new(output_dir))?,
+                None => render_man_page(&mut std::io::stdout())?,
+            }
+        }
+        Some(("verify", verify_matches)) => {
+            let store_path = verify_matches.get_one::<String>("store").unwrap();
+
+            let exit_code = verify_store(store_path);
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+        _ => {
+            // Legacy mode: direct query without subcommand
+            if let Some(query) = matches.get_one::<String>("query") {
+                run_query(&matches, query, false, false, false).await?;
+            } else {
+                eprintln!("Error: Use 'typox query' or 'typox load' subcommands, or provide --query (and --store or TYPOX_STORE) for legacy mode");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn build_cli() -> Command {
+    Command::new("typox")
         .version(VERSION)
         .about("Query and load RDF data from Oxigraph stores for Typst")
         .subcommand(
@@ -67,25 +725,60 @@ async fn main() -> Result<()> {
                         .short('s')
                         .long("store")
                         .value_name("STORE_URL_OR_PATH")
-                        .help("Oxigraph store URL (http://) or file path")
-                        .required(true),
+                        .value_hint(ValueHint::DirPath)
+                        .help("Oxigraph store URL (http://) or file path (defaults to $TYPOX_STORE)")
+                        .required(false),
                 )
                 .arg(
                     Arg::new("query")
                         .short('q')
                         .long("query")
                         .value_name("SPARQL_QUERY")
-                        .help("SPARQL SELECT query to execute")
-                        .required(true),
+                        .help("SPARQL query to execute (SELECT, ASK, CONSTRUCT or DESCRIBE)")
+                        .required_unless_present("query-file")
+                        .conflicts_with("query-file"),
+                )
+                .arg(
+                    Arg::new("query-file")
+                        .short('Q')
+                        .long("query-file")
+                        .value_name("FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("Read the SPARQL query from a file"),
+                )
+                .arg(
+                    Arg::new("strip-comments")
+                        .long("strip-comments")
+                        .help("Remove # comments from the query before running it, for endpoints that reject them")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("query-template")
+                        .long("query-template")
+                        .help("Treat the query file as a Handlebars template filled in with the --var values ({{name}}); write \\{{ for a literal {{, as in nested groups")
+                        .requires("query-file")
+                        .action(clap::ArgAction::SetTrue),
                 )
                 .arg(
                     Arg::new("output")
                         .short('o')
                         .long("output")
                         .value_name("OUTPUT_FILE")
+                        .value_hint(ValueHint::FilePath)
                         .help("Output file path (optional, defaults to stdout)")
                         .required(false),
-                ),
+                )
+                .arg(
+                    Arg::new("explain")
+                        .long("explain")
+                        .help("Print the query plan with evaluation statistics instead of the results")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(variable_arg())
+                .arg(timeout_arg())
+                .arg(output_append_arg())
+                .args(output_args())
+                .args(http_args()),
         )
         .subcommand(
             Command::new("load")
@@ -95,6 +788,7 @@ async fn main() -> Result<()> {
                         .short('s')
                         .long("store")
                         .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
                         .help("Path where to create or update the Oxigraph store")
                         .required(true),
                 )
@@ -103,10 +797,26 @@ async fn main() -> Result<()> {
                         .short('f')
                         .long("files")
                         .value_name("TURTLE_FILES")
-                        .help("Turtle files to load (supports glob patterns)")
-                        .required(true)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Turtle files to load (supports glob patterns and HTTP/HTTPS URLs); .ru files are executed as SPARQL UPDATE scripts")
+                        .required_unless_present_any(["from-endpoint", "manifest"])
                         .num_args(1..),
                 )
+                .arg(
+                    Arg::new("from-endpoint")
+                        .long("from-endpoint")
+                        .value_name("ENDPOINT_URL")
+                        .value_hint(ValueHint::Url)
+                        .help("SPARQL endpoint to run the --construct query against, loading the triples it returns")
+                        .requires("construct"),
+                )
+                .arg(
+                    Arg::new("construct")
+                        .long("construct")
+                        .value_name("SPARQL_QUERY")
+                        .help("CONSTRUCT query whose result is loaded (with --from-endpoint)")
+                        .requires("from-endpoint"),
+                )
                 .arg(
                     Arg::new("create")
                         .short('c')
@@ -114,6 +824,13 @@ async fn main() -> Result<()> {
                         .help("Create new store (removes existing store if present)")
                         .action(clap::ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("no-create")
+                        .long("no-create")
+                        .help("Fail instead of creating the store when it does not exist yet")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("create"),
+                )
                 .arg(
                     Arg::new("base-iri")
                         .short('b')
@@ -121,23 +838,255 @@ async fn main() -> Result<()> {
                         .value_name("BASE_IRI")
                         .help("Base IRI for resolving relative IRIs in Turtle files")
                         .required(false),
+                )
+                .arg(
+                    Arg::new("download-timeout")
+                        .long("download-timeout")
+                        .value_name("SECONDS")
+                        .help("Timeout for downloading files given as HTTP/HTTPS URLs")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("60"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Parse the files into a temporary in-memory store without touching STORE_PATH")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("unzip")
+                        .long("unzip")
+                        .help("Extract Zip archives and load every RDF file they contain")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("warn-duplicates")
+                        .long("warn-duplicates")
+                        .help("Print a warning for every triple that is already in the store")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("count-duplicates"),
+                )
+                .arg(
+                    Arg::new("count-duplicates")
+                        .long("count-duplicates")
+                        .help("Print how many triples of each file were already in the store")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("validate-iris")
+                        .long("validate-iris")
+                        .help("Before loading, report every IRI that is not a valid absolute IRI (RFC 3987) and load nothing if there is one")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("verify")
+                        .long("verify")
+                        .help("After loading, check with ASK queries that a sample of each file's triples (1%, or all below 1000) is in the store")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("generate-void")
+                        .long("generate-void")
+                        .help("After loading, describe the store with VoID statistics in the <http://rdfs.org/ns/void#dataset> graph")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("progress")
+                        .long("progress")
+                        .help("Print the number of triples parsed and bytes read every 10,000 triples")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("report-overlap")
+                        .long("report-overlap")
+                        .help("Before loading, report the triples each file shares with the store and the other files (holds every file in memory)")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("deduplicate-files")
+                        .long("deduplicate-files")
+                        .help("Skip files whose content (SHA-256) matches a file loaded into the same graph before them")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("graph-per-file")
+                        .long("graph-per-file")
+                        .help("Load each file into a named graph named after its IRI (file:///path/to/data.ttl)")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("graph-mapping"),
+                )
+                .arg(
+                    Arg::new("manifest")
+                        .long("manifest")
+                        .value_name("MANIFEST_FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("JSON array of load instructions, run in order: [{\"file\": \"...\", \"format\": \"turtle\", \"graph\": \"http://...\"}, {\"url\": \"...\"}]")
+                        .conflicts_with_all(["files", "from-endpoint", "graph-per-file", "graph-mapping"]),
+                )
+                .arg(
+                    Arg::new("graph-mapping")
+                        .long("graph-mapping")
+                        .value_name("MAPPING_FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("JSON file mapping file paths to named graph IRIs; unlisted files go to the default graph")
+                        .required(false),
                 ),
         )
-        // Support legacy direct query format for backwards compatibility
-        .arg(
-            Arg::new("store")
-                .short('s')
-                .long("store")
-                .value_name("STORE_URL_OR_PATH")
-                .help("Oxigraph store URL (http://) or file path")
-                .required(false),
+        .subcommand(
+            Command::new("batch")
+                .about("Run the named queries of a YAML or TOML config file, writing one result file per query")
+                .arg(
+                    Arg::new("config")
+                        .short('c')
+                        .long("config")
+                        .value_name("CONFIG_FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("YAML (.yaml, .yml) or TOML (.toml) file listing the queries to run")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("store")
+                        .short('s')
+                        .long("store")
+                        .value_name("STORE_URL_OR_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Oxigraph store URL (http://) or file path (defaults to $TYPOX_STORE)")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("output-dir")
+                        .short('d')
+                        .long("output-dir")
+                        .value_name("DIRECTORY")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Directory the result files are written to")
+                        .default_value("."),
+                )
+                .args(http_args()),
         )
-        .arg(
+        .subcommand(
+            Command::new("compact")
+                .about("Compact an Oxigraph store to reclaim disk space")
+                .arg(
+                    Arg::new("store")
+                        .short('s')
+                        .long("store")
+                        .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Path to the Oxigraph store to compact")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about("Copy every quad of several stores into one store, keeping named graphs")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Path of the store to merge into (created if missing)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("input")
+                        .short('i')
+                        .long("input")
+                        .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Store to copy from, opened read-only (repeat for each store)")
+                        .action(clap::ArgAction::Append)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("clear-graph")
+                .about("Remove every triple of one graph, without going through SPARQL UPDATE")
+                .arg(
+                    Arg::new("store")
+                        .short('s')
+                        .long("store")
+                        .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Path to the Oxigraph store")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("graph")
+                        .short('g')
+                        .long("graph")
+                        .value_name("GRAPH_IRI")
+                        .help("IRI of the named graph to clear, or `default` for the default graph")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Check that an Oxigraph store is readable (exit code 0: healthy, 1: corrupted, 2: incompatible version)")
+                .arg(
+                    Arg::new("store")
+                        .short('s')
+                        .long("store")
+                        .value_name("STORE_PATH")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Path to the Oxigraph store to verify")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .visible_alias("version")
+                .about("Print the typox and oxigraph versions and how the binary was built, for bug reports"),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script, e.g. `typox completions bash >> ~/.bashrc`")
+                .arg(
+                    Arg::new("shell")
+                        .value_name("SHELL")
+                        .help("Shell to generate completions for")
+                        .value_parser(clap::value_parser!(Shell))
+                        .required_unless_present("shell-option")
+                        .conflicts_with("shell-option"),
+                )
+                .arg(
+                    Arg::new("shell-option")
+                        .long("shell")
+                        .value_name("SHELL")
+                        .help("Same as the SHELL argument")
+                        .value_parser(clap::value_parser!(Shell)),
+                ),
+        )
+        .subcommand(
+            Command::new("man")
+                .about("Print the typox man page, or write one page per subcommand to a directory")
+                .arg(
+                    Arg::new("output-dir")
+                        .short('d')
+                        .long("output-dir")
+                        .value_name("DIRECTORY")
+                        .value_hint(ValueHint::DirPath)
+                        .help("Write typox.1 and typox-SUBCOMMAND.1 pages to DIRECTORY instead of printing typox.1")
+                        .required(false),
+                ),
+        )
+        // Support legacy direct query format for backwards compatibility
+        .arg(
+            Arg::new("store")
+                .short('s')
+                .long("store")
+                .value_name("STORE_URL_OR_PATH")
+                .value_hint(ValueHint::DirPath)
+                .help("Oxigraph store URL (http://) or file path (defaults to $TYPOX_STORE)")
+                .required(false),
+        )
+        .arg(
             Arg::new("query")
                 .short('q')
                 .long("query")
                 .value_name("SPARQL_QUERY")
-                .help("SPARQL SELECT query to execute")
+                .help("SPARQL query to execute (SELECT, ASK, CONSTRUCT or DESCRIBE)")
                 .required(false),
         )
         .arg(
@@ -145,69 +1094,670 @@ async fn main() -> Result<()> {
                 .short('o')
                 .long("output")
                 .value_name("OUTPUT_FILE")
+                .value_hint(ValueHint::FilePath)
                 .help("Output file path (optional, defaults to stdout)")
                 .required(false),
         )
-        .get_matches();
+        .arg(variable_arg())
+        .arg(timeout_arg())
+        .arg(output_append_arg())
+        .args(output_args())
+        .args(http_args())
+}
 
-    match matches.subcommand() {
-        Some(("query", query_matches)) => {
-            let store_param = query_matches.get_one::<String>("store").unwrap();
-            let query = query_matches.get_one::<String>("query").unwrap();
-            let output_file = query_matches.get_one::<String>("output");
+// Environment variables documented in the man page
+const ENVIRONMENT: &[(&str, &str)] = &[
+    ("TYPOX_STORE", "Store URL or path used when --store is not given."),
+    ("TYPOX_ENDPOINT_BEARER_TOKEN", "Bearer token sent to HTTP SPARQL endpoints."),
+    ("NO_COLOR", "Disables colored table output unless --color is given."),
+    ("XDG_CACHE_HOME", "Parent directory of the HTTP response cache (default: ~/.cache)."),
+    ("XDG_CONFIG_HOME", "Parent directory of typox/config.toml, which sets defaults (default: ~/.config)."),
+];
+
+const EXAMPLES: &[(&str, &str)] = &[
+    ("Load Turtle files into a new store:", "typox load -s ./store -f \"data/*.ttl\""),
+    (
+        "Query a store and write the results for Typst:",
+        "typox query -s ./store -q \"SELECT ?s WHERE { ?s ?p ?o }\" -o results.json",
+    ),
+    (
+        "Query a remote SPARQL endpoint:",
+        "typox query -s https://dbpedia.org/sparql -q \"ASK { ?s ?p ?o }\"",
+    ),
+    ("Run every query of a config file:", "typox batch -c queries.yaml -s ./store -d results"),
+];
+
+// The generated options and subcommand sections, followed by ENVIRONMENT and
+// EXAMPLES sections that clap knows nothing about
+fn render_man_page(writer: &mut dyn Write) -> Result<()> {
+    use clap_mangen::roff::{bold, roman, Roff};
+
+    let man = clap_mangen::Man::new(build_cli());
+    man.render_title(writer)?;
+    man.render_name_section(writer)?;
+    man.render_synopsis_section(writer)?;
+    man.render_description_section(writer)?;
+    man.render_options_section(writer)?;
+    man.render_subcommands_section(writer)?;
+
+    let mut roff = Roff::new();
+    roff.control("SH", ["ENVIRONMENT"]);
+    for (name, description) in ENVIRONMENT {
+        roff.control("TP", []).text([bold(*name)]).text([roman(*description)]);
+    }
+    roff.control("SH", ["EXAMPLES"]);
+    for (description, command) in EXAMPLES {
+        roff.control("TP", [])
+            .text([roman(*description)])
+            .control("nf", [])
+            .text([bold(*command)])
+            .control("fi", []);
+    }
+    roff.to_writer(writer)?;
+
+    man.render_version_section(writer)?;
+    Ok(())
+}
+
+fn write_man_pages(output_dir: &Path) -> Result<()> {
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create directory: {}", output_dir.display()))?;
+
+    let main_page = output_dir.join("typox.1");
+    let mut file = fs::File::create(&main_page)
+        .with_context(|| format!("Failed to write to file: {}", main_page.display()))?;
+    render_man_page(&mut file)?;
+    println!("Wrote {}", main_page.display());
+
+    // Building the command names the subcommands `typox-SUBCOMMAND`
+    let mut cli = build_cli().disable_help_subcommand(true);
+    cli.build();
+    for subcommand in cli.get_subcommands() {
+        let page = clap_mangen::Man::new(subcommand.clone())
+            .generate_to(output_dir)
+            .with_context(|| format!("Failed to write man page for: {}", subcommand.get_name()))?;
+        println!("Wrote {}", page.display());
+    }
+    Ok(())
+}
+
+fn variable_arg() -> Arg {
+    Arg::new("var")
+        .long("var")
+        .alias("variables")
+        .value_name("NAME=VALUE")
+        .help("Bind ?NAME to VALUE through a VALUES clause; values starting with http(s):// or wrapped in <> are IRIs, anything else a string (repeatable)")
+        .action(clap::ArgAction::Append)
+}
+
+fn output_append_arg() -> Arg {
+    Arg::new("output-append")
+        .long("output-append")
+        .help("Add the results to the output file instead of replacing it (JSON arrays are merged; not for sparql-json, dot or latex)")
+        .action(clap::ArgAction::SetTrue)
+        .requires("output")
+}
+
+fn timeout_arg() -> Arg {
+    Arg::new("timeout")
+        .long("timeout")
+        .value_name("SECONDS")
+        .help("Abort the query after SECONDS and exit with code 124")
+        .value_parser(clap::value_parser!(u64))
+}
+
+fn output_args() -> [Arg; 5] {
+    [
+        Arg::new("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Output format for query results")
+            .value_parser(clap::value_parser!(OutputFormat))
+            .default_value("json"),
+        Arg::new("color")
+            .long("color")
+            .help("Always color table output")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("no-color"),
+        Arg::new("no-color")
+            .long("no-color")
+            .help("Never color table output (also set by the NO_COLOR environment variable)")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("distinct")
+            .long("distinct")
+            .help("Drop result rows identical to an earlier row, for endpoints that ignore SELECT DISTINCT")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("include-schema")
+            .long("include-schema")
+            .help("Write JSON SELECT results as {\"schema\": [variables], \"data\": [rows]}")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+fn http_args() -> [Arg; 5] {
+    [
+        Arg::new("protocol")
+            .long("protocol")
+            .value_name("PROTOCOL")
+            .help("How HTTP stores are queried")
+            .value_parser(clap::value_parser!(Protocol))
+            .default_value("sparql"),
+        Arg::new("sparql-endpoint-format")
+            .long("sparql-endpoint-format")
+            .value_name("FORMAT")
+            .help("How queries are sent to HTTP endpoints")
+            .value_parser(clap::value_parser!(EndpointFormat))
+            .default_value("post-form"),
+        Arg::new("cache-ttl")
+            .long("cache-ttl")
+            .value_name("SECONDS")
+            .help("How long cached HTTP endpoint responses stay valid")
+            .value_parser(clap::value_parser!(u64))
+            .default_value("300"),
+        Arg::new("no-cache")
+            .long("no-cache")
+            .help("Always query HTTP endpoints instead of using the response cache")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("discover")
+            .long("discover")
+            .help("Find the SPARQL endpoint of an HTTP store given as a site's base URL")
+            .action(clap::ArgAction::SetTrue),
+    ]
+}
+
+// Shared by the query subcommand and legacy mode, which accept the same arguments
+async fn run_query(matches: &clap::ArgMatches, query: &str, explain: bool, template: bool, script: bool) -> Result<()> {
+    let config = UserConfig::read()?;
+    let output_file = matches.get_one::<String>("output");
+    let mut output_options = OutputOptions::from_matches(matches);
+    if matches.value_source("format") == Some(clap::parser::ValueSource::DefaultValue) {
+        output_options.format = config.default_format.unwrap_or(output_options.format);
+    }
+    let http_options = HttpOptions {
+        retries: config.http_retries,
+        ..HttpOptions::from_matches(matches)
+    };
+    let mut variables: Vec<&String> = matches.get_many::<String>("var").unwrap_or_default().collect();
+    // Template values are written into the query text instead of a VALUES clause
+    let rendered_query;
+    let query = if template {
+        rendered_query = render_query_template(query, &variables)?;
+        variables.clear();
+        rendered_query.as_str()
+    } else {
+        query
+    };
+    let statements = if script { split_sparql_script(query) } else { Vec::new() };
+    let is_script = statements.len() > 1;
+    if is_script && explain {
+        anyhow::bail!("--explain cannot be used with a SPARQL script");
+    }
+    let query = bind_variables(query, &variables)?;
+    let timeout = matches.get_one::<u64>("timeout").map(|secs| Duration::from_secs(*secs));
+    let query_timeout = QueryTimeout {
+        http: timeout.or(config.http_timeout.map(Duration::from_secs)),
+        cancellation: CancellationToken::new(),
+    };
+    let store_param = discover_store_param(config.store_param(matches), &http_options, query_timeout.http).await?;
+    let store_param = store_param.as_ref();
+
+    // Local queries are evaluated synchronously, so the deadline is enforced from a
+    // watchdog thread that cancels the evaluation, which then fails like any query
+    if let Some(timeout) = timeout {
+        let cancellation = query_timeout.cancellation.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            cancellation.cancel();
+        });
+    }
 
-            let results = execute_query(store_param, query).await?;
-            output_results(&results, output_file)?;
+    // Results for an output file are buffered and only written once the query has
+    // succeeded, so that a failing query leaves the previous file untouched.
+    // Appended JSON results are merged with the file's array, other formats are
+    // added to the end of the file.
+    let append = matches.get_flag("output-append");
+    // A second document in these formats would not be merged into the first one
+    if append && !explain && matches!(output_options.format, OutputFormat::SparqlJson | OutputFormat::Dot | OutputFormat::Latex) {
+        anyhow::bail!(
+            "--output-append cannot be used with --format {}",
+            output_options.format.to_possible_value().unwrap().get_name()
+        );
+    }
+    let merge_json = append && !explain && matches!(output_options.format, OutputFormat::Json);
+    let mut buffer = Vec::new();
+    let mut output: Box<dyn Write + '_> = match output_file {
+        Some(_) => Box::new(&mut buffer),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout())),
+    };
+    let result = if explain {
+        explain_query(store_param, &query, &mut output).await
+    } else if is_script {
+        execute_script(
+            store_param,
+            &statements,
+            &variables,
+            &config.prefixes,
+            output_options,
+            &query_timeout.cancellation,
+            &mut output,
+        )
+        .await
+    } else {
+        execute_query(
+            store_param,
+            &query,
+            &http_options,
+            &query_timeout,
+            &config.prefixes,
+            output_options,
+            &mut output,
+        )
+        .await
+    };
+    let flushed = output.flush();
+    drop(output);
+    // Exit code 124 mirrors the `timeout` command
+    if let (Err(e), Some(timeout)) = (&result, query_timeout.http) {
+        let timed_out = query_timeout.cancellation.is_cancelled()
+            || e.chain().any(|cause| cause.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout()));
+        if timed_out {
+            exit_on_timeout(timeout);
         }
-        Some(("load", load_matches)) => {
-            let store_path = load_matches.get_one::<String>("store").unwrap();
-            let files: Vec<&String> = load_matches.get_many::<String>("files").unwrap().collect();
-            let create_new = load_matches.get_flag("create");
-            let base_iri = load_matches.get_one::<String>("base-iri");
+    }
+    result?;
+    flushed.context("Failed to write query results")?;
 
-            load_turtle_files(store_path, &files, create_new, base_iri)?;
+    if let Some(file_path) = output_file {
+        if merge_json {
+            append_json_results(Path::new(file_path), &buffer)?;
+        } else {
+            write_output_file(file_path, &buffer, append)?;
         }
-        _ => {
-            // Legacy mode: direct query without subcommand
-            if let (Some(store_param), Some(query)) = (
-                matches.get_one::<String>("store"),
-                matches.get_one::<String>("query"),
-            ) {
-                let output_file = matches.get_one::<String>("output");
-                let results = execute_query(store_param, query).await?;
-                output_results(&results, output_file)?;
-            } else {
-                eprintln!("Error: Use 'typox query' or 'typox load' subcommands, or provide both --store and --query for legacy mode");
-                std::process::exit(1);
+        println!("Results written to: {}", file_path);
+    }
+    Ok(())
+}
+
+// Runs every query of the config file. A failing query is reported and the
+// remaining ones still run; the batch fails once all of them have been tried.
+async fn run_batch(matches: &clap::ArgMatches) -> Result<()> {
+    let config_path = Path::new(matches.get_one::<String>("config").unwrap());
+    let output_dir = Path::new(matches.get_one::<String>("output-dir").unwrap());
+    let user_config = UserConfig::read()?;
+    let http_options = HttpOptions {
+        retries: user_config.http_retries,
+        ..HttpOptions::from_matches(matches)
+    };
+    // Discovered once for all the queries of the batch
+    let store_param = discover_store_param(
+        user_config.store_param(matches),
+        &http_options,
+        user_config.http_timeout.map(Duration::from_secs),
+    )
+    .await?;
+
+    let config = read_batch_config(config_path)?;
+
+    let mut failed = Vec::new();
+    for entry in &config.queries {
+        let output_path = output_dir.join(entry.output_file());
+
+        match run_batch_query(store_param.as_ref(), entry, &user_config, &http_options, &output_path).await {
+            Ok(()) => println!("{}: written to {}", entry.name, output_path.display()),
+            Err(e) => {
+                eprintln!("{}: {:#}", entry.name, e);
+                failed.push(entry.name.as_str());
             }
         }
     }
 
+    println!(
+        "\nBatch complete: {} succeeded, {} failed",
+        config.queries.len() - failed.len(),
+        failed.len()
+    );
+
+    if !failed.is_empty() {
+        anyhow::bail!("Failed queries: {}", failed.join(", "));
+    }
     Ok(())
 }
 
-fn output_results(results: &Value, output_file: Option<&String>) -> Result<()> {
-    let json_output = serde_json::to_string_pretty(results)?;
+fn read_batch_config(path: &Path) -> Result<BatchConfig> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+    let config: BatchConfig = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid YAML config file: {}", path.display()))?,
+        Some("toml") => toml::from_str(&content)
+            .with_context(|| format!("Invalid TOML config file: {}", path.display()))?,
+        _ => anyhow::bail!(
+            "Unsupported config file (expected .yaml, .yml or .toml): {}",
+            path.display()
+        ),
+    };
+
+    // A config file must not write outside the output directory
+    for entry in &config.queries {
+        let output = entry.output_file();
+        let escapes = Path::new(&output)
+            .components()
+            .any(|component| !matches!(component, std::path::Component::Normal(_) | std::path::Component::CurDir));
+        if escapes {
+            anyhow::bail!(
+                "{}: output must be a relative path inside the output directory: {}",
+                entry.name,
+                output
+            );
+        }
+    }
+    Ok(config)
+}
+
+async fn run_batch_query(
+    store_param: Option<&String>,
+    entry: &BatchQuery,
+    user_config: &UserConfig,
+    http_options: &HttpOptions,
+    output_path: &Path,
+) -> Result<()> {
+    // Buffered so that a failing query never leaves a partial result file behind
+    let mut buffer = Vec::new();
+    let output_options = OutputOptions {
+        format: entry.format,
+        color: false,
+        distinct: false,
+        include_schema: false,
+    };
+    execute_query(
+        store_param,
+        &entry.query,
+        http_options,
+        &QueryTimeout {
+            http: user_config.http_timeout.map(Duration::from_secs),
+            cancellation: CancellationToken::new(),
+        },
+        &user_config.prefixes,
+        output_options,
+        &mut buffer,
+    )
+    .await?;
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::write(output_path, buffer)
+        .with_context(|| format!("Failed to write to file: {}", output_path.display()))
+}
+
+fn exit_on_timeout(timeout: Duration) -> ! {
+    eprintln!("Error: Query timed out after {} seconds", timeout.as_secs());
+    std::process::exit(124);
+}
+
+fn write_output_file(file_path: &str, contents: &[u8], append: bool) -> Result<()> {
+    fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(file_path)
+        .and_then(|mut file| file.write_all(contents))
+        .with_context(|| format!("Failed to write to file: {}", file_path))
+}
+
+// Merges new JSON results into the output file: SELECT rows are added to the
+// file's array (or to its "data" array with --include-schema, when the schemas
+// match), and ASK or CONSTRUCT results become new elements of the array
+fn append_json_results(path: &Path, new_results: &[u8]) -> Result<()> {
+    let new_results: Value = serde_json::from_slice(new_results)?;
+    let existing = match fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => serde_json::from_str(&content)
+            .with_context(|| format!("Cannot append to {}: not a JSON document", path.display()))?,
+        _ => Value::Array(Vec::new()),
+    };
+
+    let merged = match (existing, new_results) {
+        (Value::Array(mut rows), Value::Array(new_rows)) => {
+            rows.extend(new_rows);
+            Value::Array(rows)
+        }
+        (Value::Array(mut rows), result @ Value::Object(_)) if result.get("schema").is_none() => {
+            rows.push(result);
+            Value::Array(rows)
+        }
+        (Value::Object(mut existing), Value::Object(new))
+            if existing.get("schema").is_some() && existing.get("schema") == new.get("schema") =>
+        {
+            if let (Some(Value::Array(rows)), Some(Value::Array(new_rows))) =
+                (existing.get_mut("data"), new.get("data"))
+            {
+                rows.extend(new_rows.iter().cloned());
+            }
+            Value::Object(existing)
+        }
+        _ => anyhow::bail!(
+            "Cannot append to {}: its results do not have the same shape as the new ones",
+            path.display()
+        ),
+    };
+
+    let mut content = serde_json::to_string_pretty(&merged)?;
+    content.push('\n');
+    fs::write(path, content).with_context(|| format!("Failed to write to file: {}", path.display()))
+}
+
+// Write a fully built result. In NDJSON, arrays are written one element per line.
+fn write_value(value: &Value, output: OutputOptions, writer: &mut dyn Write) -> Result<()> {
+    match (output.format, value) {
+        (OutputFormat::Json, _) => {
+            serde_json::to_writer_pretty(&mut *writer, value)?;
+            writeln!(writer)?;
+        }
+        // Written from the query results themselves, never from converted JSON
+        (OutputFormat::SparqlJson | OutputFormat::Dot, _) => anyhow::bail!(
+            "--format {} is not supported here",
+            output.format.to_possible_value().unwrap().get_name()
+        ),
+        (OutputFormat::Ndjson, Value::Array(rows)) => {
+            for row in rows {
+                serde_json::to_writer(&mut *writer, row)?;
+                writeln!(writer)?;
+            }
+        }
+        (OutputFormat::Ndjson, _) => {
+            serde_json::to_writer(&mut *writer, value)?;
+            writeln!(writer)?;
+        }
+        (OutputFormat::Table, Value::Array(rows)) => {
+            write_table(rows, &table_columns(rows), output.color, writer)?;
+        }
+        // CONSTRUCT and DESCRIBE results are printed as plain Turtle
+        (OutputFormat::Table, Value::Object(object)) if object.contains_key("turtle") => {
+            write!(writer, "{}", object["turtle"].as_str().unwrap_or_default())?;
+        }
+        (OutputFormat::Table, _) => {
+            let rows = std::slice::from_ref(value);
+            write_table(rows, &table_columns(rows), output.color, writer)?;
+        }
+        (OutputFormat::Latex, Value::Array(rows)) => write_latex(rows, &table_columns(rows), writer)?,
+        (OutputFormat::Latex, Value::Object(object)) if object.contains_key("turtle") => {
+            anyhow::bail!("--format latex only applies to SELECT and ASK queries")
+        }
+        (OutputFormat::Latex, _) => {
+            let rows = std::slice::from_ref(value);
+            write_latex(rows, &table_columns(rows), writer)?;
+        }
+    }
+    Ok(())
+}
 
-    match output_file {
-        Some(file_path) => {
-            std::fs::write(file_path, json_output)
-                .with_context(|| format!("Failed to write to file: {}", file_path))?;
-            println!("Results written to: {}", file_path);
+// Columns in order of first appearance
+fn table_columns(rows: &[Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        if let Value::Object(object) = row {
+            for key in object.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
         }
-        None => {
-            println!("{}", json_output);
+    }
+    columns
+}
+
+fn write_table(
+    rows: &[Value],
+    columns: &[String],
+    color: bool,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let cells: Vec<Vec<(String, Option<&Value>)>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| {
+                    let value = row.get(column);
+                    (value.map(table_cell_text).unwrap_or_default(), value)
+                })
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].0.chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    let separator = format!("+{}+", separator.join("+"));
+
+    writeln!(writer, "{}", separator)?;
+    for (column, width) in columns.iter().zip(&widths) {
+        write!(writer, "| {:width$} ", column, width = width)?;
+    }
+    writeln!(writer, "|")?;
+    writeln!(writer, "{}", separator)?;
+
+    for row in &cells {
+        for ((text, value), width) in row.iter().zip(&widths) {
+            // Padding is added outside the escape codes so that columns stay aligned
+            let padding = " ".repeat(width - text.chars().count());
+            match value {
+                Some(value) if color => {
+                    write!(writer, "| {}{} ", text.style(table_cell_style(value)), padding)?
+                }
+                _ => write!(writer, "| {}{} ", text, padding)?,
+            }
         }
+        writeln!(writer, "|")?;
+    }
+    writeln!(writer, "{}", separator)?;
+    Ok(())
+}
+
+// One tabular row per result, headed by the variable names. Numbers go through
+// siunitx's \num{}, so documents need \usepackage{siunitx}.
+fn write_latex(rows: &[Value], columns: &[String], writer: &mut dyn Write) -> Result<()> {
+    writeln!(writer, "\\begin{{tabular}}{{{}}}", "l".repeat(columns.len()))?;
+    writeln!(writer, "\\hline")?;
+    let header: Vec<String> = columns.iter().map(|column| escape_latex(column)).collect();
+    writeln!(writer, "{} \\\\", header.join(" & "))?;
+    writeln!(writer, "\\hline")?;
+    for row in rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match row.get(column) {
+                Some(Value::Number(number)) => format!("\\num{{{}}}", number),
+                Some(value) => escape_latex(&table_cell_text(value)),
+                None => String::new(),
+            })
+            .collect();
+        writeln!(writer, "{} \\\\", cells.join(" & "))?;
     }
+    writeln!(writer, "\\hline")?;
+    writeln!(writer, "\\end{{tabular}}")?;
     Ok(())
 }
 
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '_' | '&' | '%' | '#' | '$' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn table_cell_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// Results only carry JSON values, so IRIs are recognized by their shape: an
+// absolute IRI or a `prefix:local` name
+fn table_cell_style(value: &Value) -> owo_colors::Style {
+    let style = owo_colors::Style::new();
+    match value {
+        Value::Number(_) => style.cyan(),
+        Value::String(text) if text.starts_with("_:") => style.yellow(),
+        Value::String(text) if looks_like_iri(text) => style.blue(),
+        Value::String(_) | Value::Bool(_) => style.green(),
+        _ => style,
+    }
+}
+
+fn looks_like_iri(text: &str) -> bool {
+    match text.split_once(':') {
+        Some((prefix, local)) => {
+            !text.contains(char::is_whitespace)
+                && prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+                && prefix
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+                && !local.is_empty()
+        }
+        None => false,
+    }
+}
+
+// Sources are expanded before the store is touched, so that a missing file or a
+// failed download never leaves a half-replaced store behind
 fn load_turtle_files(
     store_path: &str,
-    files: &[&String],
+    sources: &[SourceFile],
     create_new: bool,
-    base_iri: Option<&String>,
-) -> Result<()> {
+    options: &LoadOptions,
+) -> Result<Store> {
+    if store_path.starts_with(MEMORY_STORE_SCHEME) {
+        println!("Creating in-memory store: {} (discarded on exit)", store_path);
+        let store = Store::new()?;
+        load_into_store(&store, sources, options)?;
+        return Ok(store);
+    }
+
     let store_path = Path::new(store_path);
 
     // Handle store creation/cleanup
@@ -238,33 +1788,203 @@ fn load_turtle_files(
             .with_context(|| format!("Failed to open store at: {}", store_path.display()))?
     };
 
-    let mut total_triples = 0;
-    let _base_iri_str = base_iri.map(|s| s.as_str());
+    load_into_store(&store, sources, options)?;
+    Ok(store)
+}
 
-    // Load each file
-    for file_pattern in files {
-        let expanded_files = expand_glob_pattern(file_pattern)?;
+// Files with fewer triples than this are verified in full, larger ones by sampling
+// every VERIFY_SAMPLE_STEP-th triple
+const VERIFY_FULL_BELOW: usize = 1000;
+const VERIFY_SAMPLE_STEP: usize = 100;
+
+// Parses the sources again and checks with ASK queries that a sample of their
+// triples made it into the store. Blank nodes are renamed on load, so in the ASK
+// pattern they act as variables, matching any node in their position.
+fn verify_loaded_triples(store: &Store, sources: &[SourceFile], options: &LoadOptions) -> Result<()> {
+    let mut checked = 0;
+    let mut missing = 0;
+    for source in sources.iter().filter(|source| !source.is_sparql_update()) {
+        let parser = options.parser_for(source)?;
+        let quads: Vec<Quad> = parser
+            .for_reader(std::io::Cursor::new(source.read()?))
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Failed to load turtle file: {}", source))?;
+        let step = if quads.len() < VERIFY_FULL_BELOW { 1 } else { VERIFY_SAMPLE_STEP };
+
+        for quad in quads.iter().step_by(step) {
+            checked += 1;
+            let pattern = format!("{} {} {}", quad.subject, quad.predicate, quad.object);
+            let query = match &quad.graph_name {
+                GraphName::DefaultGraph => format!("ASK {{ {} }}", pattern),
+                GraphName::NamedNode(graph) => format!("ASK {{ GRAPH {} {{ {} }} }}", graph, pattern),
+                GraphName::BlankNode(_) => format!("ASK {{ GRAPH ?graph {{ {} }} }}", pattern),
+            };
+            let QueryResults::Boolean(present) =
+                SparqlEvaluator::new().parse_query(&query)?.on_store(store).execute()?
+            else {
+                anyhow::bail!("Expected ASK results");
+            };
+            if !present {
+                missing += 1;
+                eprintln!("Missing triple from {}: {}", source, quad);
+            }
+        }
+    }
+
+    if missing > 0 {
+        anyhow::bail!("Verification failed: {} of {} sampled triples are missing from the store", missing, checked);
+    }
+    println!("Verified {} sampled triples", checked);
+    Ok(())
+}
+
+// Replaces the VoID graph with a description of the rest of the store: its size,
+// distinct subjects, properties and objects, and one class partition per rdf:type
+// class with its number of instances
+fn write_void_description(store: &Store, store_path: &str) -> Result<()> {
+    let graph = NamedNode::new_unchecked(VOID_GRAPH);
+    store
+        .clear_graph(&graph)
+        .context("Failed to clear the VoID graph")?;
+
+    let dataset = if store_path.starts_with(MEMORY_STORE_SCHEME) {
+        NamedNode::new(store_path)
+    } else {
+        NamedNode::new(file_iri(Path::new(store_path))?.as_str())
+    }
+    .with_context(|| format!("Cannot derive a dataset IRI from: {}", store_path))?;
+    let void = |term: &str| NamedNode::new_unchecked(format!("{}{}", VOID_NS, term));
+
+    let mut description = vec![
+        Triple::new(dataset.clone(), NamedNode::new_unchecked(RDF_TYPE), void("Dataset")),
+        Triple::new(dataset.clone(), void("triples"), Literal::from(store.len()? as u64)),
+    ];
+
+    let counts = select_solutions(
+        store,
+        "SELECT (COUNT(DISTINCT ?s) AS ?distinctSubjects) (COUNT(DISTINCT ?p) AS ?properties) \
+         (COUNT(DISTINCT ?o) AS ?distinctObjects) \
+         WHERE { { ?s ?p ?o } UNION { GRAPH ?g { ?s ?p ?o } } }",
+    )?;
+    for solution in &counts {
+        for (variable, count) in solution.iter() {
+            description.push(Triple::new(dataset.clone(), void(variable.as_str()), count.clone()));
+        }
+    }
+
+    let classes = select_solutions(
+        store,
+        "SELECT ?class (COUNT(DISTINCT ?s) AS ?entities) \
+         WHERE { { ?s a ?class } UNION { GRAPH ?g { ?s a ?class } } } GROUP BY ?class",
+    )?;
+    description.push(Triple::new(
+        dataset.clone(),
+        void("classes"),
+        Literal::from(classes.len() as u64),
+    ));
+    for solution in &classes {
+        let (Some(class), Some(entities)) = (solution.get("class"), solution.get("entities")) else {
+            continue;
+        };
+        let partition = BlankNode::default();
+        description.push(Triple::new(dataset.clone(), void("classPartition"), partition.clone()));
+        description.push(Triple::new(partition.clone(), void("class"), class.clone()));
+        description.push(Triple::new(partition, void("entities"), entities.clone()));
+    }
 
-        for file_path in expanded_files {
-            println!("Loading file: {}", file_path.display());
+    store
+        .extend(description.into_iter().map(|triple| triple.in_graph(graph.clone())))
+        .context("Failed to write the VoID description")?;
+    store.flush()?;
 
-            let file_content = fs::read(&file_path)
-                .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    println!(
+        "VoID description of {} with {} classes written to graph <{}>",
+        dataset,
+        classes.len(),
+        VOID_GRAPH
+    );
+    Ok(())
+}
+
+// Evaluates a SELECT query and collects its solutions
+fn select_solutions(store: &Store, query: &str) -> Result<Vec<oxigraph::sparql::QuerySolution>> {
+    match SparqlEvaluator::new().parse_query(query)?.on_store(store).execute()? {
+        QueryResults::Solutions(solutions) => Ok(solutions.collect::<Result<_, _>>()?),
+        _ => anyhow::bail!("Expected SELECT results"),
+    }
+}
+
+fn load_into_store(
+    store: &Store,
+    sources: &[SourceFile],
+    options: &LoadOptions,
+) -> Result<()> {
+    if options.report_overlap {
+        print_overlap_report(store, sources, options)?;
+    }
 
-            let file_reader = std::io::Cursor::new(file_content);
+    let mut total_triples = 0;
 
+    // Load each file
+    for source in sources {
+        if source.is_sparql_update() {
+            println!("Executing SPARQL UPDATE script: {}", source);
             let triples_before = store.len()?;
+            execute_update_script(store, source)?;
+            println!(
+                "  → Store went from {} to {} triples",
+                triples_before,
+                store.len()?
+            );
+            continue;
+        }
+
+        println!("Loading file: {}", source);
 
+        let graph = options.graphs.graph_for(source)?;
+        if let Some(graph) = &graph {
+            println!("  → Graph: {}", graph);
+        }
+        let parser = source_parser(source, graph, options.base_iri.as_deref())?;
+
+        // Streamed rather than read into memory first, so that a named pipe is
+        // parsed while it is being written to. Progress counts the bytes on disk,
+        // before decompression; the size of a pipe is not known in advance.
+        let file = source.open()?;
+        let metadata = file.metadata().with_context(|| format!("Failed to read file: {}", source))?;
+        let total_bytes = metadata.is_file().then_some(metadata.len());
+        let mut progress = options.progress.then(|| LoadProgress::new(total_bytes));
+
+        let file_reader = source.decompress(ProgressReader {
+            inner: file,
+            bytes_read: progress.as_ref().map(|progress| progress.bytes_read.clone()),
+        });
+
+        let triples_before = store.len()?;
+
+        if options.duplicates != DuplicateCheck::Off {
+            let duplicate_count = insert_reporting_duplicates(
+                store,
+                source,
+                parser,
+                file_reader,
+                options.duplicates,
+                progress.as_mut(),
+            )?;
+            println!("  → {} duplicate triples", duplicate_count);
+        } else if let Some(progress) = progress.as_mut() {
+            load_with_progress(store, source, parser, file_reader, progress)?;
+        } else {
             store
-                .load_from_reader(RdfFormat::Turtle, file_reader)
-                .with_context(|| format!("Failed to load turtle file: {}", file_path.display()))?;
+                .load_from_reader(parser, file_reader)
+                .with_context(|| format!("Failed to load turtle file: {}", source))?;
+        }
 
-            let triples_after = store.len()?;
-            let new_triples = triples_after - triples_before;
-            total_triples += new_triples;
+        let triples_after = store.len()?;
+        let new_triples = triples_after - triples_before;
+        total_triples += new_triples;
 
-            println!("  → Loaded {} triples", new_triples);
-        }
+        println!("  → Loaded {} triples", new_triples);
     }
 
     println!(
@@ -276,95 +1996,1273 @@ fn load_turtle_files(
     Ok(())
 }
 
-fn expand_glob_pattern(pattern: &str) -> Result<Vec<std::path::PathBuf>> {
-    use glob::glob;
-
-    let mut paths = Vec::new();
+// The parsers already reject invalid IRIs, but stop at the first one. The sources
+// are parsed leniently here so that every invalid IRI is reported, with its file
+// and triple, before anything is written. Syntax errors still abort.
+fn validate_iris(sources: &[SourceFile], options: &LoadOptions) -> Result<()> {
+    let mut invalid = 0;
+    for source in sources.iter().filter(|source| !source.is_sparql_update()) {
+        let parser = options.parser_for(source)?.lenient();
+        for quad in parser.for_reader(std::io::Cursor::new(source.read()?)) {
+            let quad = quad.with_context(|| format!("Failed to load turtle file: {}", source))?;
+            for iri in quad_iris(&quad) {
+                if let Err(e) = oxiri::Iri::parse(iri) {
+                    invalid += 1;
+                    eprintln!("{}: invalid IRI <{}> ({}) in: {}", source, iri, e, quad);
+                }
+            }
+        }
+    }
+
+    if invalid > 0 {
+        anyhow::bail!("{} invalid IRIs found, nothing was loaded", invalid);
+    }
+    Ok(())
+}
+
+// The IRIs a quad mentions directly: its named nodes and literal datatype
+fn quad_iris(quad: &Quad) -> Vec<&str> {
+    let mut iris = vec![quad.predicate.as_str()];
+    if let NamedOrBlankNode::NamedNode(subject) = &quad.subject {
+        iris.push(subject.as_str());
+    }
+    match &quad.object {
+        Term::NamedNode(object) => iris.push(object.as_str()),
+        Term::Literal(literal) => iris.push(literal.datatype().as_str()),
+        _ => {}
+    }
+    if let GraphName::NamedNode(graph) = &quad.graph_name {
+        iris.push(graph.as_str());
+    }
+    iris
+}
+
+fn execute_update_script(store: &Store, source: &SourceFile) -> Result<()> {
+    let script = String::from_utf8(source.read()?)
+        .with_context(|| format!("SPARQL UPDATE script is not valid UTF-8: {}", source))?;
+    SparqlEvaluator::new()
+        .parse_update(&script)
+        .with_context(|| format!("Invalid SPARQL UPDATE script: {}", source))?
+        .on_store(store)
+        .execute()
+        .with_context(|| format!("Failed to execute SPARQL UPDATE script: {}", source))?;
+    Ok(())
+}
+
+fn source_parser(source: &SourceFile, graph: Option<NamedNode>, base_iri: Option<&str>) -> Result<RdfParser> {
+    let mut parser = RdfParser::from_format(source.format());
+    if let Some(base_iri) = base_iri {
+        parser = parser
+            .with_base_iri(base_iri)
+            .with_context(|| format!("Invalid base IRI: {}", base_iri))?;
+    }
+    Ok(match graph {
+        Some(graph) => parser.with_default_graph(graph),
+        None => parser,
+    })
+}
+
+// Parses every source into memory and reports, per file, how many of its triples
+// are new and how many are already in the store or in an earlier file, followed
+// by the number of triples shared by each pair of files
+fn print_overlap_report(store: &Store, sources: &[SourceFile], options: &LoadOptions) -> Result<()> {
+    let sources: Vec<&SourceFile> =
+        sources.iter().filter(|source| !source.is_sparql_update()).collect();
+    let mut quad_sets: Vec<std::collections::HashSet<Quad>> = Vec::new();
+    for &source in &sources {
+        let parser = options.parser_for(source)?;
+        let quads = parser
+            .for_reader(std::io::Cursor::new(source.read()?))
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Failed to load turtle file: {}", source))?;
+        quad_sets.push(quads);
+    }
+
+    println!("Overlap report:");
+    for (index, (source, quads)) in sources.iter().zip(&quad_sets).enumerate() {
+        let mut already_present = 0;
+        for quad in quads {
+            if quad_sets[..index].iter().any(|earlier| earlier.contains(quad)) || store.contains(quad)? {
+                already_present += 1;
+            }
+        }
+        println!(
+            "  {}: {} triples, {} new, {} already present",
+            source,
+            quads.len(),
+            quads.len() - already_present,
+            already_present
+        );
+    }
+    for (first, first_quads) in quad_sets.iter().enumerate() {
+        for (second, second_quads) in quad_sets.iter().enumerate().skip(first + 1) {
+            let shared = first_quads.intersection(second_quads).count();
+            if shared > 0 {
+                println!("  {} ∩ {}: {} shared triples", sources[first], sources[second], shared);
+            }
+        }
+    }
+    println!();
+    Ok(())
+}
+
+// Checks and inserts the quads one at a time, which is slower than bulk loading
+// but also catches duplicates within the same file. Blank nodes are renamed like
+// in a plain load, so triples with blank nodes only count as duplicates of triples
+// earlier in the same file.
+fn insert_reporting_duplicates(
+    store: &Store,
+    source: &SourceFile,
+    parser: RdfParser,
+    reader: impl std::io::Read,
+    duplicates: DuplicateCheck,
+    mut progress: Option<&mut LoadProgress>,
+) -> Result<usize> {
+    let mut duplicate_count = 0;
+    for quad in parser.rename_blank_nodes().for_reader(reader) {
+        let quad = quad.with_context(|| format!("Failed to load turtle file: {}", source))?;
+        if let Some(progress) = progress.as_deref_mut() {
+            progress.triple_parsed();
+        }
+        if store.contains(&quad)? {
+            duplicate_count += 1;
+            if duplicates == DuplicateCheck::Warn {
+                eprintln!("Warning: Duplicate triple in {}: {}", source, quad);
+            }
+        } else {
+            store.insert(&quad)?;
+        }
+    }
+    Ok(duplicate_count)
+}
+
+// Number of parsed triples between two `typox load --progress` lines
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+// Tracks how far the parser got into one file for `typox load --progress`
+struct LoadProgress {
+    bytes_read: std::rc::Rc<std::cell::Cell<u64>>,
+    total_bytes: Option<u64>,
+    triples: u64,
+}
+
+impl LoadProgress {
+    fn new(total_bytes: Option<u64>) -> Self {
+        LoadProgress {
+            bytes_read: Default::default(),
+            total_bytes,
+            triples: 0,
+        }
+    }
+
+    fn triple_parsed(&mut self) {
+        self.triples += 1;
+        if self.triples.is_multiple_of(PROGRESS_INTERVAL) {
+            let bytes_read = self.bytes_read.get();
+            match self.total_bytes {
+                Some(total_bytes) => println!(
+                    "  … {} triples, {} bytes read, {} bytes remaining",
+                    self.triples,
+                    bytes_read,
+                    total_bytes.saturating_sub(bytes_read)
+                ),
+                None => println!("  … {} triples, {} bytes read", self.triples, bytes_read),
+            }
+        }
+    }
+}
+
+// Counts the bytes handed to the parser. The parser reads ahead in blocks, so the
+// count runs slightly ahead of the triples parsed so far.
+struct ProgressReader<R> {
+    inner: R,
+    bytes_read: Option<std::rc::Rc<std::cell::Cell<u64>>>,
+}
+
+impl<R: std::io::Read> std::io::Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(bytes_read) = &self.bytes_read {
+            bytes_read.set(bytes_read.get() + read as u64);
+        }
+        Ok(read)
+    }
+}
+
+// Same as Store::load_from_reader, one transaction for the whole file, but with a
+// progress line every PROGRESS_INTERVAL triples. The store size only changes on
+// commit, so progress is measured in parsed triples.
+fn load_with_progress(
+    store: &Store,
+    source: &SourceFile,
+    parser: RdfParser,
+    reader: impl std::io::Read,
+    progress: &mut LoadProgress,
+) -> Result<()> {
+    let mut transaction = store.start_transaction()?;
+    for quad in parser.rename_blank_nodes().for_reader(reader) {
+        let quad = quad.with_context(|| format!("Failed to load turtle file: {}", source))?;
+        transaction.insert(&quad);
+        progress.triple_parsed();
+    }
+    transaction
+        .commit()
+        .with_context(|| format!("Failed to load turtle file: {}", source))?;
+    Ok(())
+}
+
+// Feeds everything written to it into a hasher, since sha2 no longer implements io::Write
+struct HashWriter(Sha256);
+
+impl std::io::Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Drops the sources whose decompressed content hashes the same as an earlier one,
+// and that go to the same graph, such as a file matched twice through a symlink
+// or a differently cased path
+fn deduplicate_sources(sources: Vec<SourceFile>, graphs: &GraphAssignment) -> Result<Vec<SourceFile>> {
+    let mut first_with_hash: HashMap<(Vec<u8>, Option<NamedNode>), String> = HashMap::new();
+    let mut kept = Vec::new();
+    for source in sources {
+        // Files are hashed as they are read rather than held in memory, and the
+        // same content loaded into another graph is not a duplicate
+        let mut hasher = HashWriter(Sha256::new());
+        std::io::copy(&mut source.decompress(source.open()?), &mut hasher)
+            .with_context(|| format!("Failed to read file: {}", source))?;
+        let key = (hasher.0.finalize().to_vec(), graphs.graph_for(&source)?);
+        match first_with_hash.get(&key) {
+            Some(first) => println!("Skipping file: {} (same content as {})", source, first),
+            None => {
+                first_with_hash.insert(key, source.to_string());
+                kept.push(source);
+            }
+        }
+    }
+    Ok(kept)
+}
+
+// Parses every file into its own in-memory store so that nothing is written to
+// disk, reporting per-file triple counts and every parse error encountered.
+fn dry_run_turtle_files(sources: &[SourceFile], base_iri: Option<&str>) -> Result<()> {
+    let mut total_triples = 0;
+    let mut failed_files = 0;
+
+    for source in sources {
+        let file_content = source.read()?;
+
+        // Scripts are only parsed: executing them needs the data they update
+        if source.is_sparql_update() {
+            let parsed = String::from_utf8(file_content)
+                .map_err(anyhow::Error::from)
+                .and_then(|script| Ok(SparqlEvaluator::new().parse_update(&script)?));
+            match parsed {
+                Ok(_) => println!("{}: SPARQL UPDATE script would be executed", source),
+                Err(e) => {
+                    failed_files += 1;
+                    eprintln!("{}: {}", source, e);
+                }
+            }
+            continue;
+        }
+
+        let store = Store::new()?;
+        match store.load_from_reader(source_parser(source, None, base_iri)?, file_content.as_slice()) {
+            Ok(()) => {
+                let triples = store.len()?;
+                total_triples += triples;
+                println!("{}: {} triples would be loaded", source, triples);
+            }
+            Err(e) => {
+                failed_files += 1;
+                eprintln!("{}: {}", source, e);
+            }
+        }
+    }
+
+    println!("\nDry run: {} total triples would be loaded", total_triples);
+
+    if failed_files > 0 {
+        anyhow::bail!("Dry run failed: {} file(s) could not be parsed", failed_files);
+    }
+
+    Ok(())
+}
+
+fn compact_store(store_path: &str) -> Result<()> {
+    let store_path = Path::new(store_path);
+    if !store_path.exists() {
+        anyhow::bail!("Store path does not exist: {}", store_path.display());
+    }
+
+    let size_before = directory_size(store_path)?;
+
+    // Scope the store so RocksDB releases its files before measuring again
+    {
+        let store = Store::open(store_path)
+            .with_context(|| format!("Failed to open store at: {}", store_path.display()))?;
+
+        println!("Compacting Oxigraph store at: {}", store_path.display());
+        store
+            .optimize()
+            .with_context(|| format!("Failed to compact store: {}", store_path.display()))?;
+        store
+            .flush()
+            .with_context(|| format!("Failed to flush store: {}", store_path.display()))?;
+    }
+
+    let size_after = directory_size(store_path)?;
+
+    println!("  Size before: {} bytes", size_before);
+    println!("  Size after:  {} bytes", size_after);
+
+    Ok(())
+}
+
+// Inputs are bulk loaded one after the other; quads already in the output are
+// skipped by the store itself, so they only show up in the duplicate count
+fn merge_stores(output_path: &str, inputs: &[&String]) -> Result<()> {
+    let output_path = Path::new(output_path);
+
+    for input in inputs {
+        let input_path = Path::new(input.as_str());
+        if !input_path.exists() {
+            anyhow::bail!("Store path does not exist: {}", input);
+        }
+        if output_path.exists() && fs::canonicalize(input_path)? == fs::canonicalize(output_path)? {
+            anyhow::bail!("Cannot merge a store into itself: {}", input);
+        }
+    }
+
+    if output_path.exists() {
+        println!("Opening existing Oxigraph store at: {}", output_path.display());
+    } else {
+        println!("Creating new Oxigraph store at: {}", output_path.display());
+    }
+    let output = Store::open(output_path)
+        .with_context(|| format!("Failed to open store at: {}", output_path.display()))?;
+
+    let quads_before = output.len()?;
+    let mut total_quads = 0;
+    for input in inputs {
+        let source = Store::open_read_only(input.as_str())
+            .with_context(|| format!("Failed to open store at: {}", input))?;
+
+        let mut quad_count = 0;
+        let mut loader = output.bulk_loader();
+        loader
+            .load_ok_quads::<_, oxigraph::store::StorageError>(
+                source.iter().inspect(|_| quad_count += 1),
+            )
+            .with_context(|| format!("Failed to copy quads from: {}", input))?;
+        loader.commit()?;
+
+        println!("  {}: {} quads", input, quad_count);
+        total_quads += quad_count;
+    }
+    output
+        .flush()
+        .with_context(|| format!("Failed to flush store: {}", output_path.display()))?;
+
+    let added = output.len()? - quads_before;
+    println!(
+        "\nMerged {} quads from {} stores ({} duplicates skipped)",
+        total_quads,
+        inputs.len(),
+        total_quads - added
+    );
+    println!("Store now contains {} quads", output.len()?);
+    Ok(())
+}
+
+// The versions below are recorded by build.rs; the WASM plugin's get_version
+// reports the same fields
+fn print_build_info() {
+    println!("typox {}", VERSION);
+    println!("api {}", API_VERSION);
+    println!("oxigraph {}", env!("TYPOX_OXIGRAPH_VERSION"));
+    println!("{}", env!("TYPOX_RUSTC_VERSION"));
+    println!("target: {}", env!("TYPOX_TARGET"));
+    println!("profile: {}", env!("TYPOX_PROFILE"));
+}
+
+fn clear_graph(store_path: &str, graph: &str) -> Result<()> {
+    let store_path = Path::new(store_path);
+    if !store_path.exists() {
+        anyhow::bail!("Store path does not exist: {}", store_path.display());
+    }
+
+    let graph_name = if graph == "default" {
+        GraphName::DefaultGraph
+    } else {
+        let iri = graph.strip_prefix('<').and_then(|g| g.strip_suffix('>')).unwrap_or(graph);
+        NamedNode::new(iri)
+            .with_context(|| format!("Invalid graph IRI: {}", graph))?
+            .into()
+    };
+
+    let store = Store::open(store_path)
+        .with_context(|| format!("Failed to open store at: {}", store_path.display()))?;
+
+    let triple_count = store
+        .quads_for_pattern(None, None, None, Some(graph_name.as_ref()))
+        .count();
+    store
+        .clear_graph(&graph_name)
+        .with_context(|| format!("Failed to clear graph: {}", graph))?;
+    store
+        .flush()
+        .with_context(|| format!("Failed to flush store: {}", store_path.display()))?;
+
+    println!("Cleared graph {} ({} triples removed)", graph, triple_count);
+    println!("Store now contains {} triples", store.len()?);
+    Ok(())
+}
+
+/// Checks a store and returns the process exit code: 0 when healthy,
+/// 1 when the store is corrupted and 2 when it was written by an incompatible Oxigraph version.
+fn verify_store(store_path: &str) -> i32 {
+    let path = Path::new(store_path);
+    if !path.exists() {
+        eprintln!("Error: Store path does not exist: {}", store_path);
+        return 1;
+    }
+
+    println!("Verifying Oxigraph store at: {}", path.display());
+
+    // Open read-only so that verification never migrates or modifies the store
+    let store = match Store::open_read_only(path) {
+        Ok(store) => store,
+        Err(e) => return report_storage_error("Failed to open store", &e),
+    };
+
+    let mut quad_count = 0;
+    for quad in store.iter() {
+        if let Err(e) = quad {
+            return report_storage_error(
+                &format!("Failed to read quad after {} quads", quad_count),
+                &e,
+            );
+        }
+        quad_count += 1;
+    }
+    println!("  → Read {} quads", quad_count);
+
+    let query_result = SparqlEvaluator::new()
+        .parse_query("SELECT * WHERE { ?s ?p ?o } LIMIT 1")
+        .map_err(|e| e.to_string())
+        .and_then(|query| query.on_store(&store).execute().map_err(|e| e.to_string()))
+        .and_then(|results| match results {
+            oxigraph::sparql::QueryResults::Solutions(solutions) => solutions
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string()),
+            _ => Ok(Vec::new()),
+        });
+    if let Err(e) = query_result {
+        eprintln!("Error: Failed to run test query: {}", e);
+        return 1;
+    }
+    println!("  → Test query succeeded");
+
+    println!("Store is healthy");
+    0
+}
+
+// Oxigraph has no dedicated error for stores written by another version: it
+// reports them as corruption, with one of these messages
+const STORE_VERSION_ERRORS: [&str; 2] = [
+    "The RocksDB database is using the outdated encoding version ",
+    "The RocksDB database is using the too recent version ",
+];
+
+fn report_storage_error(context: &str, error: &oxigraph::store::StorageError) -> i32 {
+    match error {
+        oxigraph::store::StorageError::Corruption(e)
+            if STORE_VERSION_ERRORS.iter().any(|prefix| e.to_string().starts_with(prefix)) =>
+        {
+            eprintln!("Error: {}: incompatible store version: {}", context, e);
+            2
+        }
+        oxigraph::store::StorageError::Corruption(e) => {
+            eprintln!("Error: {}: store is corrupted: {}", context, e);
+            1
+        }
+        e => {
+            eprintln!("Error: {}: {}", context, e);
+            1
+        }
+    }
+}
+
+fn directory_size(path: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory: {}", path.display()))?
+    {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+// Expands a `--files` argument and, with `--unzip`, replaces every Zip archive
+// by the RDF files it contains
+async fn expand_sources(pattern: &str, options: SourceOptions) -> Result<Vec<SourceFile>> {
+    let sources = expand_glob_pattern(pattern, options).await?;
+    if !options.unzip {
+        return Ok(sources);
+    }
+
+    let mut expanded = Vec::new();
+    for source in sources {
+        if is_zip(&source.path()) {
+            expanded.extend(extract_archive(&source)?);
+        } else {
+            expanded.push(source);
+        }
+    }
+    Ok(expanded)
+}
+
+async fn expand_glob_pattern(pattern: &str, options: SourceOptions) -> Result<Vec<SourceFile>> {
+    use glob::glob;
+
+    if pattern.starts_with("http://") || pattern.starts_with("https://") {
+        if skip_sparql_update(Path::new(pattern), pattern) {
+            return Ok(Vec::new());
+        }
+        return Ok(vec![download_source_file(pattern, options.download_timeout).await?]);
+    }
+
+    let mut paths = Vec::new();
+
+    // If pattern doesn't contain glob characters, treat as single file
+    if !pattern.contains('*') && !pattern.contains('?') && !pattern.contains('[') {
+        let path = PathBuf::from(pattern);
+        if path.exists() {
+            paths.push(SourceFile::Local(path));
+        } else {
+            anyhow::bail!("File does not exist: {}", pattern);
+        }
+        return Ok(paths);
+    }
+
+    // Use glob to expand pattern
+    for entry in glob(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))? {
+        match entry {
+            Ok(path) => {
+                if path.is_file() && !skip_sparql_update(&path, &path.display().to_string()) {
+                    // Keep Turtle files, possibly gzip-compressed, and archives
+                    // when unzipping
+                    let is_turtle = detect_format(&path) == Some(SourceFormat::Rdf(RdfFormat::Turtle));
+                    let is_archive =
+                        options.unzip && path.extension().is_some_and(|ext| ext == "zip");
+                    if is_turtle || is_archive {
+                        paths.push(SourceFile::Local(path));
+                    }
+                }
+            }
+            Err(e) => eprintln!("Warning: Error reading path: {}", e),
+        }
+    }
+
+    if paths.is_empty() {
+        anyhow::bail!("No turtle files found matching pattern: {}", pattern);
+    }
+
+    paths.sort_by_key(|source| source.path());
+    Ok(paths)
+}
+
+// What `typox load` does with a source: parse it as RDF, or execute it as a
+// SPARQL UPDATE script
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SourceFormat {
+    Rdf(RdfFormat),
+    SparqlUpdate,
+}
+
+// Generic extensions such as .txt or .json are not treated as RDF, so that
+// README files and metadata shipped inside archives are skipped. Compressed
+// files are detected from the extension in front of `.gz` (`data.ttl.gz`).
+fn detect_format(path: &Path) -> Option<SourceFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "gz" => detect_format(Path::new(path.file_stem()?)),
+        "turtle" => Some(SourceFormat::Rdf(RdfFormat::Turtle)),
+        "ru" => Some(SourceFormat::SparqlUpdate),
+        "txt" | "json" | "xml" => None,
+        extension => RdfFormat::from_extension(extension).map(SourceFormat::Rdf),
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+// Recognizes archives by their extension or, for downloads and oddly named
+// files, by the Zip local file header signature
+fn is_zip(path: &Path) -> bool {
+    use std::io::Read;
+
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+    {
+        return true;
+    }
+    // Sniffing would consume the start of a named pipe
+    if !path.is_file() {
+        return false;
+    }
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && magic == *b"PK\x03\x04"
+}
+
+// A SPARQL UPDATE script can rewrite or drop the whole store, so it only runs when
+// named explicitly as a local file, never because a glob, an archive or a download
+// happened to contain one. Returns whether the file is such a script.
+fn skip_sparql_update(name: &Path, display: &str) -> bool {
+    let is_script = detect_format(name) == Some(SourceFormat::SparqlUpdate);
+    if is_script {
+        eprintln!(
+            "Warning: Skipping SPARQL UPDATE script: {} (only local files named with -f are executed)",
+            display
+        );
+    }
+    is_script
+}
+
+fn extract_archive(source: &SourceFile) -> Result<Vec<SourceFile>> {
+    let file =
+        fs::File::open(source.path()).with_context(|| format!("Failed to read file: {}", source))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("Failed to open Zip archive: {}", source))?;
+
+    let dir = tempfile::Builder::new()
+        .prefix("typox-unzip-")
+        .tempdir()
+        .context("Failed to create temporary directory for archive")?;
+    archive
+        .extract(dir.path())
+        .with_context(|| format!("Failed to extract Zip archive: {}", source))?;
+
+    let mut files = Vec::new();
+    list_files(dir.path(), &mut files)?;
+    files.sort();
+
+    let dir = Rc::new(dir);
+    let mut sources = Vec::new();
+    for path in files {
+        let entry = path.strip_prefix(dir.path())?.to_path_buf();
+        if is_zip(&path) {
+            eprintln!(
+                "Warning: Skipping nested archive: {}/{}",
+                source,
+                entry.display()
+            );
+        } else if skip_sparql_update(&entry, &format!("{}/{}", source, entry.display())) {
+            continue;
+        } else if detect_format(&entry).is_some() {
+            sources.push(SourceFile::Extracted {
+                archive: source.to_string(),
+                entry,
+                dir: Rc::clone(&dir),
+            });
+        }
+    }
+
+    if sources.is_empty() {
+        anyhow::bail!("No RDF files found in archive: {}", source);
+    }
+    Ok(sources)
+}
+
+fn list_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory: {}", path.display()))?
+    {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+async fn download_source_file(url: &str, timeout: Duration) -> Result<SourceFile> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download: {}", url))?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Download failed with status: {} for URL: {}", response.status(), url);
+    }
+
+    save_download(url, response).await
+}
+
+// Runs a CONSTRUCT query against a SPARQL endpoint and keeps the resulting Turtle
+// document as a downloaded file, so that it is loaded like any other source
+async fn construct_source_file(endpoint_url: &str, query: &str, timeout: Duration) -> Result<SourceFile> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let mut request = client
+        .post(endpoint_url)
+        .form(&[("query", query)])
+        .header("Accept", "text/turtle");
+    if let Ok(token) = std::env::var("TYPOX_ENDPOINT_BEARER_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to send HTTP request to: {}", endpoint_url))?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("HTTP request failed with status: {} for endpoint: {}", response.status(), endpoint_url);
+    }
+
+    save_download(endpoint_url, response).await
+}
+
+async fn save_download(url: &str, response: reqwest::Response) -> Result<SourceFile> {
+    let content = response
+        .bytes()
+        .await
+        .with_context(|| format!("Failed to download: {}", url))?;
+
+    let mut file = tempfile::Builder::new()
+        .prefix("typox-download-")
+        .tempfile()
+        .context("Failed to create temporary file for download")?;
+    file.write_all(&content)
+        .with_context(|| format!("Failed to save download: {}", url))?;
+
+    Ok(SourceFile::Downloaded { url: url.to_string(), file })
+}
+
+async fn execute_query(
+    store_param: Option<&String>,
+    query: &str,
+    http_options: &HttpOptions,
+    query_timeout: &QueryTimeout,
+    user_prefixes: &HashMap<String, String>,
+    output: OutputOptions,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let timeout = query_timeout.http;
+    let cancellation = &query_timeout.cancellation;
+    let data_source = connect_to_store(store_param).await?;
+
+    // Extract prefixes from the query for URI shortening
+    let mut prefixes = user_prefixes.clone();
+    prefixes.extend(extract_prefixes(query));
+
+    match data_source {
+        // Evaluation and result iteration are synchronous. block_in_place hands this
+        // worker's other tasks to the rest of the runtime while they run; unlike
+        // spawn_blocking it allows the lazily evaluated results to keep borrowing
+        // the store and to stream into the borrowed writer.
+        DataSource::LocalStore(store) => tokio::task::block_in_place(|| {
+            execute_local_query(&store, query, &prefixes, output, cancellation, writer)
+        }),
+        DataSource::HttpEndpoint(endpoint_url) => match http_options.protocol {
+            Protocol::Sparql => {
+                let response = execute_http_query(&endpoint_url, query, http_options, timeout).await?;
+                if matches!(output.format, OutputFormat::SparqlJson) {
+                    return write_sparql_json_response(response, output.distinct, writer);
+                }
+                if matches!(output.format, OutputFormat::Dot) {
+                    let response = serde_json::to_vec(&response)?;
+                    let results = QueryResultsParser::from_format(QueryResultsFormat::Json)
+                        .for_slice(&response)
+                        .context("Invalid SPARQL JSON response format")?;
+                    return write_dot(results.into(), output.distinct, writer);
+                }
+                let variables = sparql_json_variables(&response);
+                let mut results = convert_sparql_json_to_typox_format(response, &prefixes)?;
+                if let (true, Value::Array(rows)) = (output.distinct, &mut results) {
+                    remove_duplicate_rows(rows);
+                }
+                if output.include_schema && matches!(output.format, OutputFormat::Json) && results.is_array() {
+                    results = with_schema(variables, results);
+                }
+                write_value(&results, output, writer)
+            }
+            Protocol::Gsp => {
+                let store = fetch_graph_store(&endpoint_url, http_options, timeout).await?;
+                tokio::task::block_in_place(|| {
+                    execute_local_query(&store, query, &prefixes, output, cancellation, writer)
+                })
+            }
+        },
+    }
+}
+
+fn execute_local_query(
+    store: &Store,
+    query: &str,
+    prefixes: &HashMap<String, String>,
+    output: OutputOptions,
+    cancellation: &CancellationToken,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let context = || format!("Failed to execute query: {}", query);
+    let query_results = SparqlEvaluator::new()
+        .with_cancellation_token(cancellation.clone())
+        .parse_query(query)
+        .with_context(context)?
+        .on_store(store)
+        .execute()
+        .with_context(context)?;
+    match output.format {
+        OutputFormat::SparqlJson => return write_sparql_json(query_results, output.distinct, writer),
+        OutputFormat::Dot => return write_dot(query_results, output.distinct, writer),
+        _ => {}
+    }
+    match query_results {
+        QueryResults::Solutions(solutions) => format_results(solutions, prefixes, output, writer),
+        QueryResults::Boolean(result) => write_value(&format_boolean_result(result), output, writer),
+        QueryResults::Graph(triples) => write_value(&format_graph_result(triples)?, output, writer),
+    }
+}
+
+// Downloads every graph of a Graph Store Protocol endpoint into an in-memory store.
+// N-Quads is asked for so that named graphs survive; the response's Content-Type
+// decides how it is parsed.
+async fn fetch_graph_store(
+    store_url: &str,
+    http_options: &HttpOptions,
+    timeout: Option<Duration>,
+) -> Result<Store> {
+    let mut client = reqwest::Client::builder();
+    if let Some(timeout) = timeout {
+        client = client.timeout(timeout);
+    }
+    let client = client.build().context("Failed to create HTTP client")?;
+
+    let mut request = client.get(store_url).header("Accept", "application/n-quads");
+    if let Some(token) = &http_options.bearer_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to send HTTP request to: {}", store_url))?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("HTTP request failed with status: {} for endpoint: {}", response.status(), store_url);
+    }
+
+    let format = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(RdfFormat::from_media_type)
+        .unwrap_or(RdfFormat::NQuads);
+    let body = response
+        .bytes()
+        .await
+        .with_context(|| format!("Failed to download store from: {}", store_url))?;
+
+    let store = Store::new()?;
+    store
+        .load_from_reader(format, body.as_ref())
+        .with_context(|| format!("Failed to parse store downloaded from: {}", store_url))?;
+    Ok(store)
+}
+
+// Evaluates the query to completion and prints oxigraph's plan as JSON, with the
+// number of solutions and the time spent in each step filled in
+async fn explain_query(store_param: Option<&String>, query: &str, writer: &mut dyn Write) -> Result<()> {
+    let store = match connect_to_store(store_param).await? {
+        DataSource::LocalStore(store) => store,
+        DataSource::HttpEndpoint(endpoint_url) => {
+            anyhow::bail!("--explain needs a local store, {} is a SPARQL endpoint", endpoint_url)
+        }
+    };
+
+    tokio::task::block_in_place(|| {
+        let (query_results, explanation) = SparqlEvaluator::new()
+            .parse_query(query)
+            .with_context(|| format!("Failed to execute query: {}", query))?
+            .on_store(&store)
+            .compute_statistics()
+            .explain();
+        // The statistics are only complete once every result has been read
+        match query_results.with_context(|| format!("Failed to execute query: {}", query))? {
+            QueryResults::Solutions(solutions) => {
+                for solution in solutions {
+                    solution?;
+                }
+            }
+            QueryResults::Boolean(_) => {}
+            QueryResults::Graph(triples) => {
+                for triple in triples {
+                    triple?;
+                }
+            }
+        }
+
+        let mut plan = Vec::new();
+        explanation.write_in_json(&mut plan)?;
+        let plan: Value = serde_json::from_slice(&plan)?;
+        writeln!(writer, "{}", serde_json::to_string_pretty(&plan)?)?;
+        Ok(())
+    })
+}
+
+// Falls back to the TYPOX_STORE environment variable when no --store is given
+fn resolve_store_param(store_param: Option<&String>) -> Result<String> {
+    match store_param {
+        Some(store_param) => Ok(store_param.clone()),
+        None => std::env::var("TYPOX_STORE")
+            .context("No store given: pass --store, set the TYPOX_STORE environment variable or default_store in config.toml"),
+    }
+}
+
+async fn connect_to_store(store_param: Option<&String>) -> Result<DataSource> {
+    let store_param = resolve_store_param(store_param)?;
+    let store_param = store_param.as_str();
+
+    if store_param.starts_with("http://") || store_param.starts_with("https://") {
+        // For HTTP endpoints, just return the URL - we'll validate it when executing queries
+        Ok(DataSource::HttpEndpoint(store_param.to_string()))
+    } else if store_param.starts_with(MEMORY_STORE_SCHEME) {
+        Ok(DataSource::LocalStore(Store::new()?))
+    } else {
+        let path = Path::new(store_param);
+        if !path.exists() {
+            anyhow::bail!("Store path does not exist: {}", store_param);
+        }
+        // Queries never write, and a read-only handle can't corrupt the store even
+        // if that changes by mistake
+        let store = Store::open_read_only(path)
+            .with_context(|| format!("Failed to open store at: {}", store_param))?;
+        Ok(DataSource::LocalStore(store))
+    }
+}
+
+// With --discover, an HTTP store given as a site's base URL is replaced by the
+// endpoint found there. This runs once per invocation, before any query is sent.
+async fn discover_store_param(
+    store_param: Option<String>,
+    http_options: &HttpOptions,
+    timeout: Option<Duration>,
+) -> Result<Option<String>> {
+    if !http_options.discover {
+        return Ok(store_param);
+    }
+    if matches!(http_options.protocol, Protocol::Gsp) {
+        anyhow::bail!("--discover finds SPARQL endpoints and cannot be used with --protocol gsp");
+    }
+    match store_param {
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Some(discover_sparql_endpoint(&url, http_options, timeout).await?))
+        }
+        store_param => Ok(store_param),
+    }
+}
+
+const SD_ENDPOINT: &str = "http://www.w3.org/ns/sparql-service-description#endpoint";
+
+// Looks for the SPARQL endpoint of a site: the well-known location, the
+// conventional `sparql` path, then the sd:endpoint of a SPARQL service description
+// served at the base URL itself. The path candidates are probed with an empty ASK
+// query. The endpoint found is printed so that it can be passed to --store directly.
+async fn discover_sparql_endpoint(
+    base_url: &str,
+    http_options: &HttpOptions,
+    timeout: Option<Duration>,
+) -> Result<String> {
+    let base = url::Url::parse(base_url).with_context(|| format!("Invalid URL: {}", base_url))?;
+
+    let mut client = reqwest::Client::builder();
+    if let Some(timeout) = timeout {
+        client = client.timeout(timeout);
+    }
+    let client = client.build().context("Failed to create HTTP client")?;
+    let get = |url: &url::Url| match &http_options.bearer_token {
+        Some(token) => client.get(url.clone()).bearer_auth(token),
+        None => client.get(url.clone()),
+    };
 
-    // If pattern doesn't contain glob characters, treat as single file
-    if !pattern.contains('*') && !pattern.contains('?') && !pattern.contains('[') {
-        let path = std::path::PathBuf::from(pattern);
-        if path.exists() {
-            paths.push(path);
-        } else {
-            anyhow::bail!("File does not exist: {}", pattern);
+    let mut endpoint = None;
+    for candidate in [base.join("/.well-known/sparql")?, base.join("sparql")?] {
+        let probe = get(&candidate)
+            .query(&[("query", "ASK {}")])
+            .header("Accept", "application/sparql-results+json");
+        let Ok(response) = probe.send().await else { continue };
+        if !response.status().is_success() {
+            continue;
+        }
+        // Redirects are followed, so the final URL is the endpoint itself
+        let mut url = response.url().clone();
+        let answers_ask = response
+            .json::<Value>()
+            .await
+            .is_ok_and(|results| results.get("boolean").is_some());
+        if answers_ask {
+            url.set_query(None);
+            endpoint = Some(url.to_string());
+            break;
         }
-        return Ok(paths);
     }
 
-    // Use glob to expand pattern
-    for entry in glob(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))? {
-        match entry {
-            Ok(path) => {
-                if path.is_file() {
-                    // Check if file has .ttl extension
-                    if let Some(ext) = path.extension() {
-                        if ext == "ttl" || ext == "turtle" {
-                            paths.push(path);
-                        }
-                    }
+    if endpoint.is_none() {
+        let description = get(&base)
+            .header("Accept", "text/turtle, application/rdf+xml;q=0.9")
+            .send()
+            .await;
+        if let Ok(response) = description.and_then(|response| response.error_for_status()) {
+            let format = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(RdfFormat::from_media_type)
+                .unwrap_or(RdfFormat::Turtle);
+            let body = response.bytes().await.unwrap_or_default();
+            let parser = RdfParser::from_format(format).with_base_iri(base.as_str())?;
+            endpoint = parser.for_slice(&body).filter_map(Result::ok).find_map(|quad| {
+                match (quad.predicate.as_str() == SD_ENDPOINT, quad.object) {
+                    (true, Term::NamedNode(node)) => Some(node.into_string()),
+                    _ => None,
                 }
-            }
-            Err(e) => eprintln!("Warning: Error reading path: {}", e),
+            });
         }
     }
 
-    if paths.is_empty() {
-        anyhow::bail!("No turtle files found matching pattern: {}", pattern);
-    }
+    let Some(endpoint) = endpoint else {
+        anyhow::bail!(
+            "No SPARQL endpoint found at: {} (tried /.well-known/sparql, sparql and a service description)",
+            base_url
+        );
+    };
+    eprintln!("Discovered SPARQL endpoint: {}", endpoint);
+    Ok(endpoint)
+}
 
-    paths.sort();
-    Ok(paths)
+// Statements of a SPARQL script, separated by lines holding only `---` or `;;;`.
+// Such lines inside a long string literal (""" or ''') belong to the literal.
+fn split_sparql_script(script: &str) -> Vec<String> {
+    let mut statements = vec![String::new()];
+    let mut open_quote: Option<&str> = None;
+    for line in script.lines() {
+        if open_quote.is_none() && matches!(line.trim(), "---" | ";;;") {
+            statements.push(String::new());
+            continue;
+        }
+        let statement = statements.last_mut().unwrap();
+        statement.push_str(line);
+        statement.push('\n');
+
+        let mut rest = line;
+        loop {
+            let next = match open_quote {
+                Some(quote) => rest.find(quote).map(|index| (index, None)),
+                None => ["\"\"\"", "'''"]
+                    .into_iter()
+                    .filter_map(|quote| rest.find(quote).map(|index| (index, Some(quote))))
+                    .min(),
+            };
+            let Some((index, quote)) = next else { break };
+            open_quote = quote;
+            rest = &rest[index + 3..];
+        }
+    }
+    statements.retain(|statement| !statement.trim().is_empty());
+    statements
 }
 
-async fn execute_query(store_param: &str, query: &str) -> Result<Value> {
-    let data_source = connect_to_store(store_param).await?;
+enum ScriptStatement {
+    Query(Box<PreparedSparqlQuery>),
+    Update(Box<PreparedSparqlUpdate>),
+}
 
-    // Extract prefixes from the query for URI shortening
-    let prefixes = extract_prefixes(query);
+// Runs the statements of a script in order. Every statement is parsed before the
+// first one runs, so a syntax error leaves the store untouched. Updates write to
+// the store and print nothing; the results of queries are collected into one
+// array. --var values are bound in queries only.
+async fn execute_script(
+    store_param: Option<&String>,
+    statements: &[String],
+    variables: &[&String],
+    user_prefixes: &HashMap<String, String>,
+    output: OutputOptions,
+    cancellation: &CancellationToken,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    // Results of the statements are gathered into one JSON array, which the
+    // SPARQL results and Graphviz formats cannot represent
+    if matches!(output.format, OutputFormat::SparqlJson | OutputFormat::Dot) {
+        anyhow::bail!(
+            "--format {} cannot be used with a SPARQL script, use json, ndjson, table or latex",
+            output.format.to_possible_value().unwrap().get_name()
+        );
+    }
+    let evaluator = || SparqlEvaluator::new().with_cancellation_token(cancellation.clone());
+    let mut prefixes = user_prefixes.clone();
+    let mut parsed = Vec::new();
+    for (index, statement) in statements.iter().enumerate() {
+        match evaluator().parse_query(&bind_variables(statement, variables)?) {
+            Ok(query) => {
+                prefixes.extend(extract_prefixes(statement));
+                parsed.push(ScriptStatement::Query(Box::new(query)));
+            }
+            Err(query_error) => match evaluator().parse_update(statement) {
+                Ok(update) => parsed.push(ScriptStatement::Update(Box::new(update))),
+                Err(_) => {
+                    return Err(query_error).with_context(|| {
+                        format!("Statement {} of the SPARQL script is not a valid query or update", index + 1)
+                    })
+                }
+            },
+        }
+    }
 
-    match data_source {
-        DataSource::LocalStore(store) => {
-            #[allow(deprecated)]
-            let query_results = store
-                .query(query)
-                .with_context(|| format!("Failed to execute query: {}", query))?;
-            format_results(query_results, &prefixes)
+    let has_updates = parsed.iter().any(|statement| matches!(statement, ScriptStatement::Update(_)));
+    let store = if has_updates {
+        open_store_for_updates(store_param)?
+    } else {
+        match connect_to_store(store_param).await? {
+            DataSource::LocalStore(store) => store,
+            DataSource::HttpEndpoint(endpoint_url) => {
+                anyhow::bail!("SPARQL scripts can only be run against local stores: {}", endpoint_url)
+            }
         }
-        DataSource::HttpEndpoint(endpoint_url) => {
-            execute_http_query(&endpoint_url, query, &prefixes).await
+    };
+
+    let mut results = Vec::new();
+    tokio::task::block_in_place(|| -> Result<()> {
+        for (index, statement) in parsed.into_iter().enumerate() {
+            let context = || format!("Failed to execute statement {} of the SPARQL script", index + 1);
+            match statement {
+                ScriptStatement::Update(update) => {
+                    update.on_store(&store).execute().with_context(context)?
+                }
+                ScriptStatement::Query(query) => match query.on_store(&store).execute().with_context(context)? {
+                    QueryResults::Solutions(solutions) => {
+                        for solution in solutions {
+                            let row: serde_json::Map<String, Value> = solution?
+                                .iter()
+                                .map(|(var, term)| (var.as_str().to_string(), format_term_typed(term, &prefixes)))
+                                .collect();
+                            results.push(Value::Object(row));
+                        }
+                    }
+                    QueryResults::Boolean(result) => results.push(format_boolean_result(result)),
+                    QueryResults::Graph(triples) => results.push(format_graph_result(triples)?),
+                },
+            }
         }
+        Ok(())
+    })?;
+
+    if output.distinct {
+        remove_duplicate_rows(&mut results);
     }
+    write_value(&Value::Array(results), output, writer)
 }
 
-async fn connect_to_store(store_param: &str) -> Result<DataSource> {
+// Local stores are opened read-only for queries; scripts with updates need to write
+fn open_store_for_updates(store_param: Option<&String>) -> Result<Store> {
+    let store_param = resolve_store_param(store_param)?;
     if store_param.starts_with("http://") || store_param.starts_with("https://") {
-        // For HTTP endpoints, just return the URL - we'll validate it when executing queries
-        Ok(DataSource::HttpEndpoint(store_param.to_string()))
-    } else {
-        let path = Path::new(store_param);
-        if !path.exists() {
-            anyhow::bail!("Store path does not exist: {}", store_param);
-        }
-        let store = Store::open(path).with_context(|| format!("Failed to open store at: {}", store_param))?;
-        Ok(DataSource::LocalStore(store))
+        anyhow::bail!("SPARQL UPDATE statements can only be run against local stores: {}", store_param);
+    }
+    if store_param.starts_with(MEMORY_STORE_SCHEME) {
+        return Ok(Store::new()?);
     }
+    let path = Path::new(&store_param);
+    if !path.exists() {
+        anyhow::bail!("Store path does not exist: {}", store_param);
+    }
+    Store::open(path).with_context(|| format!("Failed to open store at: {}", store_param))
 }
 
-async fn execute_http_query(endpoint_url: &str, query: &str, prefixes: &HashMap<String, String>) -> Result<Value> {
-    let client = reqwest::Client::new();
+// Returns the SPARQL JSON results document, from the cache when it holds a fresh copy
+async fn execute_http_query(
+    endpoint_url: &str,
+    query: &str,
+    http_options: &HttpOptions,
+    timeout: Option<Duration>,
+) -> Result<Value> {
+    let cache_path = if http_options.cache_enabled { http_cache_path(endpoint_url, query, http_options) } else { None };
+
+    if let Some(cached) = cache_path.as_deref().and_then(|path| read_http_cache(path, http_options.cache_ttl)) {
+        return Ok(cached);
+    }
 
-    // Create form data for SPARQL query
-    let mut form = HashMap::new();
-    form.insert("query", query);
+    let mut client = reqwest::Client::builder();
+    if let Some(timeout) = timeout {
+        client = client.timeout(timeout);
+    }
+    let client = client.build().context("Failed to create HTTP client")?;
+
+    let request = match http_options.endpoint_format {
+        EndpointFormat::PostForm => client.post(endpoint_url).form(&[("query", query)]),
+        EndpointFormat::PostBody => client
+            .post(endpoint_url)
+            .header("Content-Type", "application/sparql-query")
+            .body(query.to_string()),
+        EndpointFormat::Get => client.get(endpoint_url).query(&[("query", query)]),
+    };
 
-    let response = client
-        .post(endpoint_url)
-        .form(&form)
-        .header("Accept", "application/sparql-results+json")
-        .send()
-        .await
-        .with_context(|| format!("Failed to send HTTP request to: {}", endpoint_url))?;
+    let request = match &http_options.bearer_token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+
+    let request = request.header("Accept", SPARQL_RESULTS_ACCEPT);
+    let mut attempt = 0;
+    let response = loop {
+        let attempt_request = request.try_clone().context("HTTP request cannot be retried")?;
+        let error = match attempt_request.send().await {
+            Ok(response) if response.status().is_server_error() && attempt < http_options.retries => {
+                format!("status {}", response.status())
+            }
+            Ok(response) => break response,
+            Err(e) if attempt < http_options.retries => e.to_string(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to send HTTP request to: {}", endpoint_url))
+            }
+        };
+        attempt += 1;
+        eprintln!(
+            "Warning: request to {} failed ({}), retrying ({}/{})",
+            endpoint_url, error, attempt, http_options.retries
+        );
+        tokio::time::sleep(Duration::from_millis(500 * u64::from(attempt))).await;
+    };
 
     if !response.status().is_success() {
         anyhow::bail!("HTTP request failed with status: {} for endpoint: {}", response.status(), endpoint_url);
@@ -375,11 +3273,82 @@ async fn execute_http_query(endpoint_url: &str, query: &str, prefixes: &HashMap<
         .await
         .with_context(|| "Failed to parse JSON response from HTTP endpoint")?;
 
-    // Convert SPARQL JSON response directly to our target format
-    convert_sparql_json_to_typox_format(json_response, prefixes)
+    if let Some(path) = cache_path {
+        if let Err(e) = write_http_cache(&path, &json_response) {
+            eprintln!("Warning: failed to cache HTTP response: {:#}", e);
+        }
+    }
+
+    Ok(json_response)
+}
+
+const SPARQL_RESULTS_ACCEPT: &str = "application/sparql-results+json";
+
+// Cache entries live in ~/.cache/typox/ (or $XDG_CACHE_HOME/typox/), keyed by
+// the SHA-256 of the endpoint URL, query, request format and bearer token, so
+// that a response fetched with one token is never served to another or to an
+// unauthenticated run
+fn http_cache_path(endpoint_url: &str, query: &str, http_options: &HttpOptions) -> Option<PathBuf> {
+    let cache_root = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(endpoint_url.as_bytes());
+    hasher.update([0]);
+    hasher.update(query.as_bytes());
+    hasher.update([0]);
+    hasher.update(SPARQL_RESULTS_ACCEPT.as_bytes());
+    hasher.update([0]);
+    hasher.update(http_options.endpoint_format.to_possible_value().unwrap().get_name().as_bytes());
+    if let Some(token) = &http_options.bearer_token {
+        hasher.update([0]);
+        hasher.update(Sha256::digest(token.as_bytes()));
+    }
+    let key: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+
+    Some(cache_root.join("typox").join(format!("{}.json", key)))
+}
+
+// Returns the cached response if it exists and is younger than the TTL
+fn read_http_cache(path: &Path, ttl: Duration) -> Option<Value> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    if age >= ttl {
+        return None;
+    }
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+fn write_http_cache(path: &Path, response: &Value) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create cache directory: {}", parent.display()))?;
+    }
+    fs::write(path, serde_json::to_vec(response)?)
+        .with_context(|| format!("Failed to write cache file: {}", path.display()))
+}
+
+// The SELECT variables listed in the head of a SPARQL JSON results document
+fn sparql_json_variables(json: &Value) -> Vec<String> {
+    json.pointer("/head/vars")
+        .and_then(Value::as_array)
+        .map(|vars| vars.iter().filter_map(|var| var.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+// Wraps SELECT rows for --include-schema, so that templates know every variable
+// and its position even when the first rows leave some of them unbound
+fn with_schema(variables: Vec<String>, rows: Value) -> Value {
+    serde_json::json!({ "schema": variables, "data": rows })
 }
 
 fn convert_sparql_json_to_typox_format(json: Value, prefixes: &HashMap<String, String>) -> Result<Value> {
+    // ASK queries return a boolean instead of bindings
+    if let Some(result) = json.get("boolean").and_then(|b| b.as_bool()) {
+        return Ok(format_boolean_result(result));
+    }
+
     if let Some(results) = json.get("results").and_then(|r| r.get("bindings")) {
         if let Some(bindings_array) = results.as_array() {
             let mut json_array = Vec::new();
@@ -389,11 +3358,8 @@ fn convert_sparql_json_to_typox_format(json: Value, prefixes: &HashMap<String, S
                     let mut row_object = serde_json::Map::new();
 
                     for (var, value_obj) in binding_obj {
-                        if let Some(value_map) = value_obj.as_object() {
-                            if let Some(value_str) = value_map.get("value").and_then(|v| v.as_str()) {
-                                let formatted_value = format_http_term_typed(value_str, value_map, prefixes);
-                                row_object.insert(var.clone(), formatted_value);
-                            }
+                        if let Some(formatted_value) = format_http_term_typed(value_obj, prefixes) {
+                            row_object.insert(var.clone(), formatted_value);
                         }
                     }
 
@@ -412,208 +3378,551 @@ fn convert_sparql_json_to_typox_format(json: Value, prefixes: &HashMap<String, S
     anyhow::bail!("Invalid SPARQL JSON response format")
 }
 
-fn format_http_term_typed(value_str: &str, value_map: &serde_json::Map<String, Value>, prefixes: &HashMap<String, String>) -> Value {
-    match value_map.get("type").and_then(|t| t.as_str()) {
-        Some("uri") => {
-            // Try to shorten URI using known prefixes
-            for (prefix, namespace) in prefixes {
-                if value_str.starts_with(namespace) {
-                    return Value::String(format!("{}:{}", prefix, &value_str[namespace.len()..]));
-                }
-            }
-            Value::String(value_str.to_string())
-        }
-        Some("bnode") => Value::String(format!("_:{}", value_str)),
-        Some("literal") => {
-            // Handle datatyped literals
-            if let Some(datatype) = value_map.get("datatype").and_then(|dt| dt.as_str()) {
-                match datatype {
-                    "http://www.w3.org/2001/XMLSchema#integer"
-                    | "http://www.w3.org/2001/XMLSchema#int"
-                    | "http://www.w3.org/2001/XMLSchema#long"
-                    | "http://www.w3.org/2001/XMLSchema#short"
-                    | "http://www.w3.org/2001/XMLSchema#byte"
-                    | "http://www.w3.org/2001/XMLSchema#nonNegativeInteger"
-                    | "http://www.w3.org/2001/XMLSchema#positiveInteger"
-                    | "http://www.w3.org/2001/XMLSchema#unsignedInt"
-                    | "http://www.w3.org/2001/XMLSchema#unsignedLong"
-                    | "http://www.w3.org/2001/XMLSchema#unsignedShort"
-                    | "http://www.w3.org/2001/XMLSchema#unsignedByte" => {
-                        if let Ok(num) = value_str.parse::<i64>() {
-                            return Value::Number(serde_json::Number::from(num));
-                        }
-                    }
-                    "http://www.w3.org/2001/XMLSchema#decimal"
-                    | "http://www.w3.org/2001/XMLSchema#double"
-                    | "http://www.w3.org/2001/XMLSchema#float" => {
-                        if let Ok(num) = value_str.parse::<f64>() {
-                            if let Some(json_num) = serde_json::Number::from_f64(num) {
-                                return Value::Number(json_num);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+// Converts a term of a SPARQL JSON results document, going through format_value
+// like local results so that both sources give the same JSON
+fn format_http_term_typed(value_obj: &Value, prefixes: &HashMap<String, String>) -> Option<Value> {
+    let value_map = value_obj.as_object()?;
+    let value = value_map.get("value")?;
+    let term_type = value_map.get("type").and_then(|t| t.as_str());
+
+    // RDF 1.2 triple terms carry an object of subject/predicate/object terms
+    if term_type == Some("triple") {
+        let triple = value.as_object()?;
+        return Some(format_triple(
+            format_http_term_typed(triple.get("subject")?, prefixes)?,
+            format_http_term_typed(triple.get("predicate")?, prefixes)?,
+            format_http_term_typed(triple.get("object")?, prefixes)?,
+        ));
+    }
+
+    let value = value.as_str()?;
+    let language = value_map.get("xml:lang").and_then(|lang| lang.as_str());
+    let kind = match term_type {
+        Some("uri") => TermKind::Iri,
+        Some("bnode") => TermKind::BlankNode,
+        // "typed-literal" is used by endpoints following the 2007 SPARQL JSON draft
+        Some("literal") | Some("typed-literal") => TermKind::Literal {
+            datatype: value_map.get("datatype").and_then(|dt| dt.as_str()).unwrap_or(
+                if language.is_some() { RDF_LANG_STRING } else { XSD_STRING },
+            ),
+            language,
+        },
+        _ => return Some(Value::String(value.to_string())),
+    };
+    Some(format_value(value, kind, prefixes))
+}
+
+// JSON output collects all rows into an array; NDJSON streams each row as soon as
+// it is produced so that memory use stays constant
+fn format_results(
+    solutions: QuerySolutionIter,
+    prefixes: &HashMap<String, String>,
+    output: OutputOptions,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    // Tables follow the SELECT clause order rather than the JSON key order
+    let columns: Vec<String> = solutions
+        .variables()
+        .iter()
+        .map(|variable| variable.as_str().to_string())
+        .collect();
+    let mut json_array = Vec::new();
+    let mut row_count = 0;
+    let mut seen_rows = std::collections::HashSet::new();
+
+    for solution in solutions {
+        let solution = solution?;
+        let mut row_object = serde_json::Map::new();
+
+        for (var, term) in solution.iter() {
+            let value = format_term_typed(term, prefixes);
+            row_object.insert(var.as_str().to_string(), value);
+        }
+
+        row_count += 1;
+        if output.distinct && !seen_rows.insert(Value::Object(row_object.clone()).to_string()) {
+            continue;
+        }
+        match output.format {
+            OutputFormat::Json
+            | OutputFormat::Table
+            | OutputFormat::SparqlJson
+            | OutputFormat::Dot
+            | OutputFormat::Latex => json_array.push(Value::Object(row_object)),
+            OutputFormat::Ndjson => {
+                serde_json::to_writer(&mut *writer, &row_object)?;
+                writeln!(writer)?;
             }
+        }
+    }
 
-            // Try to parse as number if no explicit datatype or if parsing failed
-            if let Ok(num) = value_str.parse::<i64>() {
-                Value::Number(serde_json::Number::from(num))
-            } else if let Ok(num) = value_str.parse::<f64>() {
-                if let Some(json_num) = serde_json::Number::from_f64(num) {
-                    Value::Number(json_num)
-                } else {
-                    Value::String(value_str.to_string())
+    if row_count == 0 {
+        anyhow::bail!("No records found for the given query");
+    }
+
+    match output.format {
+        OutputFormat::Json if output.include_schema => {
+            write_value(&with_schema(columns, Value::Array(json_array)), output, writer)?
+        }
+        OutputFormat::Json | OutputFormat::SparqlJson | OutputFormat::Dot => {
+            write_value(&Value::Array(json_array), output, writer)?
+        }
+        OutputFormat::Table => write_table(&json_array, &columns, output.color, writer)?,
+        OutputFormat::Latex => write_latex(&json_array, &columns, writer)?,
+        OutputFormat::Ndjson => {}
+    }
+    Ok(())
+}
+
+// SPARQL JSON is serialized from the terms rather than converted back from the
+// typox rows, which have lost the difference between IRIs and strings and the
+// datatypes of numbers
+fn write_sparql_json(results: QueryResults, distinct: bool, writer: &mut dyn Write) -> Result<()> {
+    let serializer = QueryResultsSerializer::from_format(QueryResultsFormat::Json);
+    match results {
+        QueryResults::Solutions(solutions) => {
+            let mut solutions_writer =
+                serializer.serialize_solutions_to_writer(Vec::new(), solutions.variables().to_vec())?;
+            let mut row_count = 0;
+            let mut seen_rows = std::collections::HashSet::new();
+            for solution in solutions {
+                let solution = solution?;
+                row_count += 1;
+                if distinct && !seen_rows.insert(solution.values().to_vec()) {
+                    continue;
                 }
-            } else {
-                Value::String(value_str.to_string())
+                solutions_writer.serialize(solution.iter())?;
             }
+            if row_count == 0 {
+                anyhow::bail!("No records found for the given query");
+            }
+            writer.write_all(&solutions_writer.finish()?)?;
+        }
+        QueryResults::Boolean(result) => {
+            serializer.serialize_boolean_to_writer(&mut *writer, result)?;
+        }
+        QueryResults::Graph(_) => {
+            anyhow::bail!("--format sparql-json only applies to SELECT and ASK queries")
         }
-        _ => Value::String(value_str.to_string())
     }
+    writeln!(writer)?;
+    Ok(())
 }
 
-fn format_results(
-    results: oxigraph::sparql::QueryResults,
-    prefixes: &HashMap<String, String>,
-) -> Result<Value> {
-    match results {
-        oxigraph::sparql::QueryResults::Solutions(solutions) => {
-            let mut json_array = Vec::new();
+// Endpoints already answer in SPARQL JSON, so their response is passed through
+fn write_sparql_json_response(mut response: Value, distinct: bool, writer: &mut dyn Write) -> Result<()> {
+    if let Some(Value::Array(bindings)) = response.pointer_mut("/results/bindings") {
+        if bindings.is_empty() {
+            anyhow::bail!("No records found for the given query");
+        }
+        if distinct {
+            remove_duplicate_rows(bindings);
+        }
+    }
+    serde_json::to_writer(&mut *writer, &response)?;
+    writeln!(writer)?;
+    Ok(())
+}
 
+// Each CONSTRUCT triple, or each SELECT row binding ?source and ?target, becomes an
+// edge. Nodes are identified by their full term and labeled with the IRI's local
+// name, so the output can be piped to `dot -Tsvg`.
+fn write_dot(results: QueryResults, distinct: bool, writer: &mut dyn Write) -> Result<()> {
+    let mut graph = DotGraph::default();
+    match results {
+        QueryResults::Solutions(solutions) => {
+            let [source, label, target] = ["source", "label", "target"].map(|name| {
+                solutions.variables().iter().position(|variable| variable.as_str() == name)
+            });
+            let (Some(source), Some(label), Some(target)) = (source, label, target) else {
+                anyhow::bail!("--format dot needs the SELECT variables ?source, ?label and ?target");
+            };
+            let mut row_count = 0;
             for solution in solutions {
                 let solution = solution?;
-                let mut row_object = serde_json::Map::new();
-
-                for (var, term) in solution.iter() {
-                    let value = format_term_typed(term, prefixes);
-                    row_object.insert(var.as_str().to_string(), value);
+                row_count += 1;
+                let values = solution.values();
+                if let (Some(source), Some(target)) = (&values[source], &values[target]) {
+                    graph.add_edge(source, values[label].as_ref(), target, distinct);
                 }
-
-                json_array.push(Value::Object(row_object));
             }
-
-            if json_array.is_empty() {
+            if row_count == 0 {
                 anyhow::bail!("No records found for the given query");
             }
+        }
+        QueryResults::Graph(triples) => {
+            for triple in triples {
+                let triple = triple?;
+                let predicate = Term::from(triple.predicate);
+                graph.add_edge(&triple.subject.into(), Some(&predicate), &triple.object, distinct);
+            }
+        }
+        QueryResults::Boolean(_) => {
+            anyhow::bail!("--format dot only applies to SELECT and CONSTRUCT queries")
+        }
+    }
+    writer.write_all(graph.finish().as_bytes())?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct DotGraph {
+    statements: Vec<String>,
+    nodes: std::collections::HashSet<String>,
+    edges: std::collections::HashSet<String>,
+}
 
-            Ok(Value::Array(json_array))
+impl DotGraph {
+    fn add_edge(&mut self, source: &Term, label: Option<&Term>, target: &Term, distinct: bool) {
+        let source = self.node(source);
+        let target = self.node(target);
+        let mut edge = format!("{} -> {}", source, target);
+        if let Some(label) = label {
+            edge.push_str(&format!(" [label={}]", dot_string(&term_local_name(label))));
         }
-        _ => {
-            anyhow::bail!("Only SELECT queries are supported");
+        if !distinct || self.edges.insert(edge.clone()) {
+            self.statements.push(edge);
+        }
+    }
+
+    // Declares the node with its label the first time it is seen
+    fn node(&mut self, term: &Term) -> String {
+        let id = dot_string(&term.to_string());
+        if self.nodes.insert(id.clone()) {
+            self.statements
+                .push(format!("{} [label={}]", id, dot_string(&term_local_name(term))));
+        }
+        id
+    }
+
+    fn finish(self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for statement in self.statements {
+            dot.push_str(&format!("  {};\n", statement));
         }
+        dot.push_str("}\n");
+        dot
     }
 }
 
-fn format_term_typed(term: &Term, prefixes: &HashMap<String, String>) -> Value {
+// The part of an IRI after its last `#` or `/`, or a literal's lexical form
+fn term_local_name(term: &Term) -> String {
     match term {
         Term::NamedNode(node) => {
-            let uri = node.as_str();
-            // Try to shorten URI using known prefixes
-            for (prefix, namespace) in prefixes {
-                if uri.starts_with(namespace) {
-                    return Value::String(format!("{}:{}", prefix, &uri[namespace.len()..]));
-                }
-            }
-            Value::String(uri.to_string())
-        }
-        Term::BlankNode(node) => Value::String(format!("_:{}", node.as_str())),
-        Term::Literal(literal) => {
-            // Remove language tags, return just the value
-            let value_str = literal.value();
-
-            // Check if it's a numeric literal
-            let datatype = literal.datatype();
-            let datatype_str = datatype.as_str();
-            match datatype_str {
-                XSD_INTEGER
-                | XSD_INT
-                | XSD_LONG
-                | XSD_SHORT
-                | XSD_BYTE
-                | XSD_NON_NEGATIVE_INTEGER
-                | XSD_POSITIVE_INTEGER
-                | XSD_UNSIGNED_INT
-                | XSD_UNSIGNED_LONG
-                | XSD_UNSIGNED_SHORT
-                | XSD_UNSIGNED_BYTE => {
-                    if let Ok(num) = value_str.parse::<i64>() {
-                        return Value::Number(serde_json::Number::from(num));
-                    }
-                }
-                XSD_DECIMAL | XSD_DOUBLE | XSD_FLOAT => {
-                    if let Ok(num) = value_str.parse::<f64>() {
-                        if let Some(json_num) = serde_json::Number::from_f64(num) {
-                            return Value::Number(json_num);
-                        }
-                    }
-                }
-                _ => {}
+            let iri = node.as_str();
+            match iri.trim_end_matches(['#', '/']).rsplit_once(['#', '/']) {
+                Some((_, local_name)) if !local_name.is_empty() => local_name.to_string(),
+                _ => iri.to_string(),
             }
+        }
+        Term::Literal(literal) => literal.value().to_string(),
+        _ => term.to_string(),
+    }
+}
 
-            // Try to parse as number if no explicit datatype
-            if let Ok(num) = value_str.parse::<i64>() {
-                Value::Number(serde_json::Number::from(num))
-            } else if let Ok(num) = value_str.parse::<f64>() {
-                if let Some(json_num) = serde_json::Number::from_f64(num) {
-                    Value::Number(json_num)
-                } else {
-                    Value::String(value_str.to_string())
-                }
-            } else {
-                Value::String(value_str.to_string())
-            }
+fn dot_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+// Keeps the first occurrence of every row. Rows are compared through their
+// serialized form, in which keys are always sorted.
+fn remove_duplicate_rows(rows: &mut Vec<Value>) {
+    let mut seen_rows = std::collections::HashSet::new();
+    rows.retain(|row| seen_rows.insert(row.to_string()));
+}
+
+fn format_boolean_result(result: bool) -> Value {
+    serde_json::json!({ "result": Value::Bool(result) })
+}
+
+// CONSTRUCT and DESCRIBE results are returned as a Turtle document
+fn format_graph_result(triples: QueryTripleIter) -> Result<Value> {
+    let mut serializer = RdfSerializer::from_format(RdfFormat::Turtle).for_writer(Vec::new());
+    for triple in triples {
+        serializer.serialize_triple(&triple?)?;
+    }
+    let turtle = String::from_utf8(serializer.finish()?)
+        .context("Serialized Turtle is not valid UTF-8")?;
+    Ok(serde_json::json!({ "type": "graph", "turtle": turtle }))
+}
+
+fn literal_to_json(literal: TypedLiteral) -> Value {
+    if literal.overflows_i64() {
+        eprintln!(
+            "Warning: {} does not fit in a 64-bit integer, it is written as a string",
+            literal.value
+        );
+    }
+    literal.to_json_value()
+}
+
+#[derive(Clone, Copy)]
+enum TermKind<'a> {
+    Iri,
+    BlankNode,
+    Literal { datatype: &'a str, language: Option<&'a str> },
+}
+
+// The single conversion from an RDF term to JSON, used for both local and HTTP results
+fn format_value(value: &str, kind: TermKind, prefixes: &HashMap<String, String>) -> Value {
+    match kind {
+        TermKind::Iri => {
+            // Try to shorten URI using known prefixes. The longest matching namespace
+            // wins, as in the plugin, and ties go to the alphabetically last prefix.
+            let shortened = prefixes
+                .iter()
+                .filter(|(_, namespace)| value.starts_with(namespace.as_str()))
+                .max_by_key(|(prefix, namespace)| (namespace.len(), *prefix))
+                .map(|(prefix, namespace)| format!("{}:{}", prefix, &value[namespace.len()..]));
+            Value::String(shortened.unwrap_or_else(|| value.to_string()))
+        }
+        TermKind::BlankNode => Value::String(format!("_:{}", value)),
+        TermKind::Literal { datatype, language } => {
+            literal_to_json(TypedLiteral::new(value, datatype, language))
+        }
+    }
+}
+
+// RDF 1.2 triple terms are rendered as nested objects
+fn format_triple(subject: Value, predicate: Value, object: Value) -> Value {
+    serde_json::json!({ "subject": subject, "predicate": predicate, "object": object })
+}
+
+fn format_term_typed(term: &Term, prefixes: &HashMap<String, String>) -> Value {
+    match term {
+        Term::NamedNode(node) => format_value(node.as_str(), TermKind::Iri, prefixes),
+        Term::BlankNode(node) => format_value(node.as_str(), TermKind::BlankNode, prefixes),
+        Term::Literal(literal) => format_value(
+            literal.value(),
+            TermKind::Literal {
+                datatype: literal.datatype().as_str(),
+                language: literal.language(),
+            },
+            prefixes,
+        ),
+        Term::Triple(triple) => format_triple(
+            format_term_typed(&triple.subject.clone().into(), prefixes),
+            format_term_typed(&triple.predicate.clone().into(), prefixes),
+            format_term_typed(&triple.object, prefixes),
+        ),
+    }
+}
+
+// Appends a VALUES clause binding the `--var` arguments. Values are turned into
+// validated IRIs or escaped string literals, so they can never change the
+// structure of the query.
+fn bind_variables(query: &str, variables: &[&String]) -> Result<String> {
+    if variables.is_empty() {
+        return Ok(query.to_string());
+    }
+
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    for variable in variables {
+        let (name, value) = parse_variable(variable)?;
+        if names.contains(&name) {
+            anyhow::bail!("Variable ?{} is bound more than once", name);
+        }
+        names.push(name);
+        values.push(variable_term(name, value)?);
+    }
+
+    // The clause goes on its own line so that a trailing comment in the query
+    // cannot swallow it
+    Ok(format!(
+        "{}\nVALUES ({}) {{ ({}) }}",
+        query,
+        names
+            .iter()
+            .map(|name| format!("?{}", name))
+            .collect::<Vec<_>>()
+            .join(" "),
+        values.join(" ")
+    ))
+}
+
+// Splits a `--var NAME=VALUE` argument, accepting `?NAME` and `$NAME` as well
+fn parse_variable(variable: &str) -> Result<(&str, &str)> {
+    let (name, value) = variable
+        .split_once('=')
+        .with_context(|| format!("Invalid --var (expected NAME=VALUE): {}", variable))?;
+    let name = name.trim_start_matches(['?', '$']);
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        anyhow::bail!("Invalid variable name in --var: {}", variable);
+    }
+    Ok((name, value))
+}
+
+// Values starting with http(s):// or wrapped in <> become validated IRIs, anything
+// else an escaped string literal
+fn variable_term(name: &str, value: &str) -> Result<String> {
+    if let Some(iri) = value.strip_prefix('<').and_then(|v| v.strip_suffix('>')) {
+        Ok(NamedNode::new(iri)
+            .with_context(|| format!("Invalid IRI for ?{}: {}", name, value))?
+            .to_string())
+    } else if value.starts_with("http://") || value.starts_with("https://") {
+        Ok(NamedNode::new(value)
+            .with_context(|| format!("Invalid IRI for ?{}: {}", name, value))?
+            .to_string())
+    } else {
+        Ok(Literal::new_simple_literal(value).to_string())
+    }
+}
+
+// Fills in `{{name}}` placeholders with the --var values, turned into SPARQL terms
+// as for VALUES bindings. A value already written as a literal ("hello"@en,
+// "42"^^xsd:integer with a full datatype IRI) is validated and kept as it is.
+// Placeholders without a value are an error rather than left empty.
+fn render_query_template(template: &str, variables: &[&String]) -> Result<String> {
+    let mut data = serde_json::Map::new();
+    for variable in variables {
+        let (name, value) = parse_variable(variable)?;
+        let term = if value.starts_with('"') {
+            value
+                .parse::<Literal>()
+                .with_context(|| format!("Invalid literal for {}: {}", name, value))?
+                .to_string()
+        } else {
+            variable_term(name, value)?
+        };
+        if data.insert(name.to_string(), Value::String(term)).is_some() {
+            anyhow::bail!("Template variable {} is given more than once", name);
         }
     }
+
+    let mut handlebars = handlebars::Handlebars::new();
+    handlebars.set_strict_mode(true);
+    // Terms are already escaped for SPARQL, HTML escaping would corrupt them
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars
+        .render_template(template, &data)
+        .context("Failed to render query template")
 }
 
 fn extract_prefixes(query: &str) -> HashMap<String, String> {
     let mut prefixes = HashMap::new();
 
     // Add common prefixes
-    prefixes.insert(
-        "rdf".to_string(),
-        "http://www.w3.org/1999/02/22-rdf-syntax-ns#".to_string(),
-    );
-    prefixes.insert(
-        "rdfs".to_string(),
-        "http://www.w3.org/2000/01/rdf-schema#".to_string(),
-    );
-    prefixes.insert(
-        "owl".to_string(),
-        "http://www.w3.org/2002/07/owl#".to_string(),
-    );
-    prefixes.insert("xsd".to_string(), XSD_NS.to_string());
-    prefixes.insert("foaf".to_string(), "http://xmlns.com/foaf/0.1/".to_string());
-    prefixes.insert(
-        "dc".to_string(),
-        "http://purl.org/dc/elements/1.1/".to_string(),
-    );
-    prefixes.insert(
-        "dcterms".to_string(),
-        "http://purl.org/dc/terms/".to_string(),
-    );
-    prefixes.insert(
-        "skos".to_string(),
-        "http://www.w3.org/2004/02/skos/core#".to_string(),
-    );
+    for (prefix, namespace) in COMMON_PREFIXES {
+        prefixes.insert(prefix.to_string(), namespace.to_string());
+    }
 
-    // Parse PREFIX declarations from the query
-    for line in query.lines() {
-        let line = line.trim();
-        if line.to_uppercase().starts_with("PREFIX") {
-            if let Some(rest) = line.get(6..).map(|s| s.trim()) {
-                if let Some(colon_pos) = rest.find(':') {
-                    let prefix = rest[..colon_pos].trim();
-                    let remainder = rest[colon_pos + 1..].trim();
-                    if remainder.starts_with('<') && remainder.ends_with('>') {
-                        let namespace = &remainder[1..remainder.len() - 1];
-                        prefixes.insert(prefix.to_string(), namespace.to_string());
-                    }
-                }
+    // Read the PREFIX and BASE declarations of the query prologue. Namespaces
+    // given as relative IRIs are resolved against the BASE declared before them.
+    let mut tokens = prologue_tokens(query);
+    let mut base: Option<String> = None;
+    while let Some(keyword) = tokens.next() {
+        if keyword.eq_ignore_ascii_case("PREFIX") {
+            let (Some(name), Some(iri)) = (tokens.next(), tokens.next()) else { break };
+            let (Some(prefix), Some(namespace)) = (name.strip_suffix(':'), resolve_iri(iri, base.as_deref()))
+            else {
+                break;
+            };
+            prefixes.insert(prefix.to_string(), namespace);
+        } else if keyword.eq_ignore_ascii_case("BASE") {
+            match tokens.next().and_then(|iri| resolve_iri(iri, base.as_deref())) {
+                Some(iri) => base = Some(iri),
+                None => break,
             }
+        } else {
+            break;
         }
     }
 
     prefixes
 }
+
+// Splits the start of a query into keywords, prefix names and `<IRI>`s, skipping
+// whitespace and `#` comments. Only meant for the prologue: string literals and
+// other SPARQL tokens are not recognised.
+fn prologue_tokens(query: &str) -> impl Iterator<Item = &str> {
+    let mut rest = query;
+    std::iter::from_fn(move || {
+        loop {
+            rest = rest.trim_start();
+            match rest.strip_prefix('#') {
+                Some(comment) => rest = comment.find('\n').map_or("", |end| &comment[end..]),
+                None => break,
+            }
+        }
+        if rest.is_empty() {
+            return None;
+        }
+        let end = if rest.starts_with('<') {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else {
+            rest.find(|c: char| c.is_whitespace() || c == '<' || c == '#').unwrap_or(rest.len())
+        };
+        let (token, remainder) = rest.split_at(end);
+        rest = remainder;
+        Some(token)
+    })
+}
+
+// Removes `#` comments from a query, up to the end of their line. A `#` inside an
+// IRI (`<http://example.org/#frag>`) or a string literal is kept: IRIs and strings,
+// including the triple-quoted forms, are skipped over as a whole. A `<` that does
+// not open a valid IRI is a comparison operator.
+fn strip_comments(query: &str) -> String {
+    let mut stripped = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some(start) = rest.find(['#', '<', '"', '\'']) {
+        let (before, from) = rest.split_at(start);
+        stripped.push_str(before);
+        let end = match from.as_bytes()[0] {
+            b'#' => {
+                let comment_end = from.find('\n').unwrap_or(from.len());
+                rest = &from[comment_end..];
+                continue;
+            }
+            b'<' => from[1..]
+                .find(|c: char| c == '>' || c.is_whitespace() || "<\"{}|^`\\".contains(c))
+                .filter(|&i| from[1..][i..].starts_with('>'))
+                .map_or(1, |i| i + 2),
+            quote => {
+                let delimiter = if from.len() >= 3 && from.as_bytes()[..3].iter().all(|&b| b == quote) {
+                    &from[..3]
+                } else {
+                    &from[..1]
+                };
+                string_literal_end(from, delimiter)
+            }
+        };
+        stripped.push_str(&from[..end]);
+        rest = &from[end..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+// Length of the string literal at the start of `literal`, up to and including the
+// closing delimiter, or the whole input when it is unterminated
+fn string_literal_end(literal: &str, delimiter: &str) -> usize {
+    let mut offset = delimiter.len();
+    while offset < literal.len() {
+        let remainder = &literal[offset..];
+        if let Some(escaped) = remainder.strip_prefix('\\') {
+            offset += 1 + escaped.chars().next().map_or(0, char::len_utf8);
+        } else if remainder.starts_with(delimiter) {
+            return offset + delimiter.len();
+        } else {
+            offset += remainder.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    literal.len()
+}
+
+// Absolute IRIs are kept as written so that they match result IRIs exactly; only
+// relative ones go through URL resolution
+fn resolve_iri(token: &str, base: Option<&str>) -> Option<String> {
+    let iri = token.strip_prefix('<')?.strip_suffix('>')?;
+    if url::Url::parse(iri).is_ok() {
+        return Some(iri.to_string());
+    }
+    let base = url::Url::parse(base?).ok()?;
+    Some(base.join(iri).ok()?.to_string())
+}
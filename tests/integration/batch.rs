@@ -0,0 +1,112 @@
+use crate::{typox, TestStore};
+use predicates::str::contains;
+use serde_json::json;
+
+#[test]
+fn yaml_config_writes_one_file_per_query() {
+    let store = TestStore::load(&["people.ttl"]);
+    let config = store.dir.path().join("queries.yaml");
+    std::fs::write(
+        &config,
+        r#"queries:
+  - name: names
+    query: |
+      PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+      SELECT ?name WHERE { ?p foaf:name ?name } ORDER BY ?name
+  - name: ages
+    query: "PREFIX foaf: <http://xmlns.com/foaf/0.1/> SELECT ?age WHERE { ?p foaf:age ?age } ORDER BY ?age"
+    format: ndjson
+    output: people/ages.txt
+"#,
+    )
+    .unwrap();
+
+    typox(&store.dir)
+        .args(["batch", "-c"])
+        .arg(&config)
+        .arg("-s")
+        .arg(store.path())
+        .arg("-d")
+        .arg(store.dir.path().join("results"))
+        .assert()
+        .success()
+        .stdout(contains("Batch complete: 2 succeeded, 0 failed"));
+
+    let names: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(store.dir.path().join("results/names.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        names,
+        json!([{ "name": "Alice" }, { "name": "Bob" }, { "name": "Carol" }])
+    );
+    assert_eq!(
+        std::fs::read_to_string(store.dir.path().join("results/people/ages.txt")).unwrap(),
+        "{\"age\":27}\n{\"age\":34}\n{\"age\":41}\n"
+    );
+}
+
+#[test]
+fn toml_config_failures_do_not_abort_the_batch() {
+    let store = TestStore::load(&["people.ttl"]);
+    let config = store.dir.path().join("queries.toml");
+    std::fs::write(
+        &config,
+        r#"[[queries]]
+name = "broken"
+query = "SELECT ?s WHERE { ?s ?p "
+
+[[queries]]
+name = "count"
+query = "SELECT (COUNT(*) AS ?n) WHERE { ?s ?p ?o }"
+"#,
+    )
+    .unwrap();
+    let results = store.dir.path().join("results");
+
+    typox(&store.dir)
+        .args(["batch", "-c"])
+        .arg(&config)
+        .arg("-s")
+        .arg(store.path())
+        .arg("-d")
+        .arg(&results)
+        .assert()
+        .failure()
+        .stdout(contains("Batch complete: 1 succeeded, 1 failed"))
+        .stderr(contains("broken: Failed to execute query"))
+        .stderr(contains("Failed queries: broken"));
+
+    assert!(!results.join("broken.json").exists());
+    assert!(results.join("count.json").exists());
+}
+
+#[test]
+fn outputs_outside_the_output_directory_are_rejected() {
+    let store = TestStore::load(&["people.ttl"]);
+    let config = store.dir.path().join("queries.toml");
+    for output in ["../escaped.json", "/tmp/escaped.json"] {
+        std::fs::write(
+            &config,
+            format!(
+                "[[queries]]\nname = \"count\"\nquery = \"SELECT (COUNT(*) AS ?n) WHERE {{ ?s ?p ?o }}\"\noutput = \"{}\"\n",
+                output
+            ),
+        )
+        .unwrap();
+
+        typox(&store.dir)
+            .args(["batch", "-c"])
+            .arg(&config)
+            .arg("-s")
+            .arg(store.path())
+            .arg("-d")
+            .arg(store.dir.path().join("results"))
+            .assert()
+            .failure()
+            .stderr(contains(
+                "count: output must be a relative path inside the output directory",
+            ));
+    }
+    assert!(!store.dir.path().join("escaped.json").exists());
+}
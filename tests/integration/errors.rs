@@ -0,0 +1,146 @@
+use crate::{typox, TestStore};
+use oxigraph::io::RdfFormat;
+use predicates::str::contains;
+use tempfile::TempDir;
+
+#[test]
+fn missing_store_path_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["query", "-s"])
+        .arg(dir.path().join("does-not-exist"))
+        .args(["-q", "SELECT * WHERE { ?s ?p ?o }"])
+        .assert()
+        .failure()
+        .stderr(contains("Store path does not exist"));
+}
+
+#[test]
+fn missing_store_without_environment_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["query", "-q", "SELECT * WHERE { ?s ?p ?o }"])
+        .assert()
+        .failure()
+        .stderr(contains("set the TYPOX_STORE environment variable"));
+}
+
+#[test]
+fn invalid_sparql_fails() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .args(["-q", "SELECT ?s WHERE { ?s ?p "])
+        .assert()
+        .failure()
+        .stderr(contains("Failed to execute query"));
+}
+
+#[test]
+fn missing_arguments_print_usage_hint() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .assert()
+        .code(1)
+        .stderr(contains("Use 'typox query' or 'typox load' subcommands"));
+}
+
+#[test]
+fn verify_reports_healthy_store() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["verify", "-s"])
+        .arg(store.path())
+        .assert()
+        .code(0)
+        .stdout(contains("Read 15 quads"))
+        .stdout(contains("Store is healthy"));
+}
+
+#[test]
+fn verify_missing_store_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["verify", "-s"])
+        .arg(dir.path().join("does-not-exist"))
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn compact_keeps_store_queryable() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["compact", "-s"])
+        .arg(store.path())
+        .assert()
+        .success();
+    let results = store.query("SELECT (COUNT(*) AS ?n) WHERE { ?s ?p ?o }");
+    assert_eq!(results, serde_json::json!([{ "n": 15 }]));
+}
+
+#[test]
+fn clear_graph_removes_only_that_graph() {
+    let store = TestStore::from_fixture("graphs.nq", RdfFormat::NQuads);
+    typox(&store.dir)
+        .args(["clear-graph", "-s"])
+        .arg(store.path())
+        .args(["-g", "http://example.org/graphs/hr"])
+        .assert()
+        .success()
+        .stdout(contains("3 triples removed"))
+        .stdout(contains("Store now contains 2 triples"));
+
+    typox(&store.dir)
+        .args(["clear-graph", "-s"])
+        .arg(store.path())
+        .args(["-g", "default"])
+        .assert()
+        .success()
+        .stdout(contains("1 triples removed"));
+
+    let results =
+        store.query("SELECT ?g (COUNT(*) AS ?n) WHERE { GRAPH ?g { ?s ?p ?o } } GROUP BY ?g");
+    assert_eq!(
+        results,
+        serde_json::json!([{ "g": "ex:graphs/projects", "n": 1 }])
+    );
+}
+
+#[test]
+fn completions_are_printed_for_each_shell() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(contains("_typox()"))
+        .stdout(contains("--store"));
+    typox(&dir)
+        .args(["completions", "--shell", "fish"])
+        .assert()
+        .success()
+        .stdout(contains("complete -c typox"));
+}
+
+#[test]
+fn man_page_documents_environment() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .arg("man")
+        .assert()
+        .success()
+        .stdout(contains(".TH typox 1"))
+        .stdout(contains("TYPOX_ENDPOINT_BEARER_TOKEN"))
+        .stdout(contains(".SH EXAMPLES"));
+
+    let man_dir = dir.path().join("man1");
+    typox(&dir)
+        .args(["man", "--output-dir"])
+        .arg(&man_dir)
+        .assert()
+        .success();
+    assert!(man_dir.join("typox.1").exists());
+    assert!(man_dir.join("typox-query.1").exists());
+}
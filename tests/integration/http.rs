@@ -0,0 +1,538 @@
+use crate::typox;
+use predicates::str::contains;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::matchers::{body_string, body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const QUERY: &str =
+    "PREFIX ex: <http://example.org/>\nSELECT ?person ?age WHERE { ?person ex:age ?age }";
+
+fn sparql_json_response() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "head": { "vars": ["person", "age"] },
+        "results": {
+            "bindings": [{
+                "person": { "type": "uri", "value": "http://example.org/alice" },
+                "age": {
+                    "type": "literal",
+                    "value": "34",
+                    "datatype": "http://www.w3.org/2001/XMLSchema#integer"
+                }
+            }]
+        }
+    }))
+}
+
+// The binary is run synchronously, so the mock server needs its own worker thread
+async fn run_query(
+    server: &MockServer,
+    cache_dir: &TempDir,
+    extra_args: &[&str],
+) -> assert_cmd::assert::Assert {
+    let mut cmd = typox(cache_dir);
+    cmd.args(["query", "-s", &server.uri(), "-q", QUERY])
+        .args(extra_args);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_endpoint_results_are_converted() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("accept", "application/sparql-results+json"))
+        .and(body_string_contains("query="))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let assert = run_query(&server, &dir, &[]).await.success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(results, json!([{ "person": "ex:alice", "age": 34 }]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_ask_result() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "head": {}, "boolean": true })),
+        )
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let assert = run_query(&server, &dir, &[]).await.success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(results, json!({ "result": true }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bearer_token_is_sent_from_environment() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("authorization", "Bearer s3cret"))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let mut cmd = typox(&dir);
+    cmd.env("TYPOX_ENDPOINT_BEARER_TOKEN", "s3cret").args([
+        "query",
+        "-s",
+        &server.uri(),
+        "-q",
+        QUERY,
+    ]);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_error_status_fails() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &[])
+        .await
+        .failure()
+        .stderr(contains("HTTP request failed with status: 500"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_responses_are_cached() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &[]).await.success();
+    run_query(&server, &dir, &[]).await.success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cached_responses_are_keyed_by_bearer_token() {
+    let server = MockServer::start().await;
+    // Once with the token and once without: the second run must not reuse the first
+    // response, while the third, with the token again, is served from the cache
+    Mock::given(method("POST"))
+        .respond_with(sparql_json_response())
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    for token in [Some("s3cret"), None, Some("s3cret")] {
+        let mut cmd = typox(&dir);
+        cmd.args(["query", "-s", &server.uri(), "-q", QUERY]);
+        if let Some(token) = token {
+            cmd.env("TYPOX_ENDPOINT_BEARER_TOKEN", token);
+        }
+        tokio::task::spawn_blocking(move || cmd.assert())
+            .await
+            .unwrap()
+            .success();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn no_cache_always_queries_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(sparql_json_response())
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--no-cache"]).await.success();
+    run_query(&server, &dir, &["--no-cache"]).await.success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn post_form_encodes_reserved_characters() {
+    let query = "SELECT ?s WHERE { ?s ?p \"a&b=c+d#e\" }";
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(body_string(
+            "query=SELECT+%3Fs+WHERE+%7B+%3Fs+%3Fp+%22a%26b%3Dc%2Bd%23e%22+%7D",
+        ))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let mut cmd = typox(&dir);
+    cmd.args(["query", "-s", &server.uri(), "-q", query]);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn post_body_endpoint_format() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("content-type", "application/sparql-query"))
+        .and(body_string(QUERY))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--sparql-endpoint-format", "post-body"])
+        .await
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_endpoint_format() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("query", QUERY))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--sparql-endpoint-format", "get"])
+        .await
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn load_downloads_url_sources() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<http://example.org/alice> <http://example.org/age> 34 .\n"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let store_path = dir.path().join("store");
+    let mut cmd = typox(&dir);
+    cmd.args(["load", "-s"])
+        .arg(&store_path)
+        .args(["-f", &format!("{}/data.ttl", server.uri())]);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success()
+        .stdout(contains("Loaded 1 triples"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_timeout_exits_with_code_124() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(sparql_json_response().set_delay(std::time::Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--timeout", "1"])
+        .await
+        .code(124)
+        .stderr(contains("Query timed out after 1 seconds"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_terms_are_converted_like_local_terms() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "head": { "vars": ["label", "count", "node", "statement"] },
+            "results": {
+                "bindings": [{
+                    "label": { "type": "literal", "value": "42", "xml:lang": "en" },
+                    "count": {
+                        "type": "typed-literal",
+                        "value": "18446744073709551615",
+                        "datatype": "http://www.w3.org/2001/XMLSchema#unsignedLong"
+                    },
+                    "node": { "type": "bnode", "value": "b0" },
+                    "statement": {
+                        "type": "triple",
+                        "value": {
+                            "subject": { "type": "uri", "value": "http://example.org/alice" },
+                            "predicate": { "type": "uri", "value": "http://example.org/age" },
+                            "object": {
+                                "type": "literal",
+                                "value": "34",
+                                "datatype": "http://www.w3.org/2001/XMLSchema#integer"
+                            }
+                        }
+                    }
+                }]
+            }
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let assert = run_query(&server, &dir, &[]).await.success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        results,
+        json!([{
+            "label": "42",
+            "count": "18446744073709551615",
+            "node": "_:b0",
+            "statement": { "subject": "ex:alice", "predicate": "ex:age", "object": 34 }
+        }])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gsp_protocol_queries_downloaded_dataset() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(header("accept", "application/n-quads"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<http://example.org/alice> <http://example.org/age> \"34\"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example.org/graphs/hr> .\n\
+             <http://example.org/bob> <http://example.org/age> \"27\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n",
+            "application/n-quads; charset=utf-8",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let mut cmd = typox(&dir);
+    cmd.args(["query", "--protocol", "gsp", "-s", &server.uri(), "-q"])
+        .arg("SELECT ?g ?age WHERE { GRAPH ?g { ?person <http://example.org/age> ?age } }");
+    let assert = tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        results,
+        json!([{ "g": "http://example.org/graphs/hr", "age": 34 }])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn load_from_endpoint_stores_construct_result() {
+    let construct = "CONSTRUCT { ?person <http://example.org/age> ?age } WHERE { ?person <http://example.org/age> ?age }";
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("accept", "text/turtle"))
+        .and(body_string_contains("query=CONSTRUCT"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<http://example.org/alice> <http://example.org/age> 34 .\n\
+             <http://example.org/bob> <http://example.org/age> 27 .\n",
+            "text/turtle",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let mut cmd = typox(&dir);
+    cmd.args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .args(["--from-endpoint", &server.uri(), "--construct", construct]);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success()
+        .stdout(contains("Loaded 2 triples"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_file_comments_are_stripped() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string(
+            "PREFIX ex: <http://example.org/#>\n\n\
+             SELECT ?s WHERE {\n  \
+             ?s ex:tag \"#1\" ; ex:note '''multi\n# line''' . \n  \
+             FILTER(?x < 5 && <http://example.org/a#b> != ?s) \n\
+             }\n",
+        ))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let query_file = dir.path().join("query.rq");
+    std::fs::write(
+        &query_file,
+        "PREFIX ex: <http://example.org/#>\n\
+         # Tagged resources\n\
+         SELECT ?s WHERE {\n  \
+         ?s ex:tag \"#1\" ; ex:note '''multi\n# line''' . # inline comment\n  \
+         FILTER(?x < 5 && <http://example.org/a#b> != ?s) # comparison\n\
+         }\n",
+    )
+    .unwrap();
+    let mut cmd = typox(&dir);
+    cmd.args(["query", "--strip-comments", "-s", &server.uri()])
+        .args(["--sparql-endpoint-format", "post-body", "--query-file"])
+        .arg(&query_file);
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn distinct_removes_duplicate_endpoint_rows() {
+    let server = MockServer::start().await;
+    let alice = json!({ "person": { "type": "uri", "value": "http://example.org/alice" } });
+    let bob = json!({ "person": { "type": "uri", "value": "http://example.org/bob" } });
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "head": { "vars": ["person"] },
+            "results": { "bindings": [alice, bob, alice, alice] }
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let assert = run_query(&server, &dir, &["--distinct"]).await.success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        results,
+        json!([{ "person": "ex:alice" }, { "person": "ex:bob" }])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn configured_retries_repeat_failed_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(sparql_json_response())
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("typox")).unwrap();
+    std::fs::write(dir.path().join("typox/config.toml"), "http_retries = 2\n").unwrap();
+    run_query(&server, &dir, &[])
+        .await
+        .success()
+        .stderr(contains("retrying (1/2)"))
+        .stdout(contains("\"age\": 34"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn discover_finds_endpoint_below_base_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/sparql"))
+        .and(query_param("query", "ASK {}"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "head": {}, "boolean": true })),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/sparql"))
+        .respond_with(sparql_json_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--discover"])
+        .await
+        .success()
+        .stderr(contains(format!(
+            "Discovered SPARQL endpoint: {}/sparql",
+            server.uri()
+        )))
+        .stdout(contains("\"age\": 34"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn discover_runs_once_per_batch() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/sparql"))
+        .and(query_param("query", "ASK {}"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "head": {}, "boolean": true })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/sparql"))
+        .respond_with(sparql_json_response())
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("queries.yaml");
+    std::fs::write(
+        &config,
+        format!(
+            "queries:\n  - name: first\n    query: {:?}\n  - name: second\n    query: {:?}\n",
+            QUERY, QUERY
+        ),
+    )
+    .unwrap();
+    let mut cmd = typox(&dir);
+    cmd.args([
+        "batch",
+        "--discover",
+        "--no-cache",
+        "-s",
+        &server.uri(),
+        "-c",
+    ])
+    .arg(&config)
+    .arg("-d")
+    .arg(dir.path().join("results"));
+    tokio::task::spawn_blocking(move || cmd.assert())
+        .await
+        .unwrap()
+        .success()
+        .stdout(contains("Batch complete: 2 succeeded, 0 failed"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn discover_rejects_graph_store_protocol() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    run_query(&server, &dir, &["--discover", "--protocol", "gsp"])
+        .await
+        .failure()
+        .stderr(contains(
+            "--discover finds SPARQL endpoints and cannot be used with --protocol gsp",
+        ));
+}
@@ -0,0 +1,17 @@
+use crate::typox;
+use predicates::str::contains;
+use tempfile::TempDir;
+
+#[test]
+fn info_reports_linked_versions() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .arg("info")
+        .assert()
+        .success()
+        .stdout(contains("typox 0.1.0"))
+        .stdout(contains("api 1."))
+        .stdout(contains("oxigraph 0.5."))
+        .stdout(contains("rustc "))
+        .stdout(contains("profile: "));
+}
@@ -0,0 +1,687 @@
+use crate::{fixture, typox, TestStore};
+use predicates::prelude::*;
+use predicates::str::contains;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn load_turtle_reports_triple_count() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .assert()
+        .success()
+        .stdout(contains("Loaded 15 triples"))
+        .stdout(contains("Store now contains 15 triples"));
+}
+
+#[test]
+fn load_ntriples_file() {
+    let store = TestStore::load(&["people.nt"]);
+    let results = store.query("SELECT ?name WHERE { ?p foaf:name ?name }");
+    assert_eq!(results, serde_json::json!([{ "name": "Dave" }]));
+}
+
+#[test]
+fn load_appends_to_existing_store() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["load", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stdout(contains("Opening existing Oxigraph store"))
+        .stdout(contains("Store now contains 18 triples"));
+}
+
+#[test]
+fn load_create_replaces_existing_store() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["load", "--create", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stdout(contains("Removing existing store"))
+        .stdout(contains("Store now contains 3 triples"));
+}
+
+#[test]
+fn load_glob_pattern_loads_turtle_files_only() {
+    let dir = TempDir::new().unwrap();
+    let pattern = fixture("extra").join("*");
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(pattern)
+        .assert()
+        .success()
+        .stdout(contains("books.ttl"))
+        .stdout(contains("films.turtle"))
+        .stdout(contains("notes.txt").not())
+        .stdout(contains("Store now contains 3 triples"));
+}
+
+#[test]
+fn load_glob_without_matches_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("extra").join("*.nq"))
+        .assert()
+        .failure()
+        .stderr(contains("No turtle files found matching pattern"));
+}
+
+#[test]
+fn load_missing_file_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("missing.ttl"))
+        .assert()
+        .failure()
+        .stderr(contains("File does not exist"));
+}
+
+#[test]
+fn load_invalid_turtle_fails() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("invalid.ttl"))
+        .assert()
+        .failure()
+        .stderr(contains("Failed to load turtle file"));
+}
+
+#[test]
+fn dry_run_does_not_create_store() {
+    let dir = TempDir::new().unwrap();
+    let store_path = dir.path().join("store");
+    typox(&dir)
+        .args(["load", "--dry-run", "-s"])
+        .arg(&store_path)
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stdout(contains("people.ttl: 15 triples would be loaded"))
+        .stdout(contains("people.nt: 3 triples would be loaded"))
+        .stdout(contains("Dry run: 18 total triples would be loaded"));
+    assert!(!store_path.exists());
+}
+
+#[test]
+fn dry_run_reports_parse_errors_with_line() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "--dry-run", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("invalid.ttl"))
+        .assert()
+        .failure()
+        .stdout(contains("people.ttl: 15 triples would be loaded"))
+        .stderr(contains("invalid.ttl: Parser error at line 5"))
+        .stderr(contains("1 file(s) could not be parsed"));
+}
+
+#[test]
+fn base_iri_resolves_relative_iris() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    let data = store.dir.path().join("relative.ttl");
+    std::fs::write(&data, "<alice> <knows> <bob> .\n").unwrap();
+    for extra in [&["--dry-run"][..], &[]] {
+        typox(&store.dir)
+            .args(["load", "--base-iri", "http://example.org/", "-s"])
+            .arg(store.path())
+            .arg("-f")
+            .arg(&data)
+            .args(extra)
+            .assert()
+            .success()
+            .stdout(contains("1 triples"));
+    }
+
+    assert_eq!(
+        store.query("SELECT ?s ?o WHERE { ?s <http://example.org/knows> ?o }"),
+        json!([{ "s": "ex:alice", "o": "ex:bob" }])
+    );
+}
+
+// Writes a Zip archive holding the given fixtures under the given entry names
+fn write_archive(path: &std::path::Path, entries: &[(&str, &[u8])]) {
+    use std::io::Write;
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, content) in entries {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+#[test]
+fn unzip_loads_rdf_files_from_archive() {
+    let dir = TempDir::new().unwrap();
+    let archive = dir.path().join("bundle.zip");
+    write_archive(
+        &archive,
+        &[
+            ("people.ttl", &std::fs::read(fixture("people.ttl")).unwrap()),
+            (
+                "more/people.nt",
+                &std::fs::read(fixture("people.nt")).unwrap(),
+            ),
+            ("README.txt", b"not RDF"),
+            ("inner.zip", b"PK\x03\x04"),
+        ],
+    );
+    typox(&dir)
+        .args(["load", "--unzip", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&archive)
+        .assert()
+        .success()
+        .stdout(contains("bundle.zip/more/people.nt"))
+        .stdout(contains("README.txt").not())
+        .stderr(contains("Skipping nested archive"))
+        .stdout(contains("Store now contains 18 triples"));
+}
+
+#[test]
+fn unzip_detects_archives_by_magic_bytes() {
+    let dir = TempDir::new().unwrap();
+    let archive = dir.path().join("download");
+    write_archive(
+        &archive,
+        &[("people.ttl", &std::fs::read(fixture("people.ttl")).unwrap())],
+    );
+    typox(&dir)
+        .args(["load", "--unzip", "--dry-run", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&archive)
+        .assert()
+        .success()
+        .stdout(contains("Dry run: 15 total triples would be loaded"));
+}
+
+#[test]
+fn gzip_files_are_decompressed() {
+    use std::io::Write;
+    let dir = TempDir::new().unwrap();
+    let compressed = dir.path().join("people.nt.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        std::fs::File::create(&compressed).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder
+        .write_all(&std::fs::read(fixture("people.nt")).unwrap())
+        .unwrap();
+    encoder.finish().unwrap();
+
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&compressed)
+        .assert()
+        .success()
+        .stdout(contains("Loaded 3 triples"));
+}
+
+#[test]
+fn load_into_memory_store_writes_nothing() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .current_dir(dir.path())
+        .args(["load", "-s", "memory://scratch", "-f"])
+        .arg(fixture("people.ttl"))
+        .assert()
+        .success()
+        .stdout(contains("Store now contains 15 triples"));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn warn_duplicates_reports_each_existing_triple() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["load", "--warn-duplicates", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stderr(contains("Duplicate triple in").count(15))
+        .stderr(contains(
+            "<http://example.org/alice> <http://xmlns.com/foaf/0.1/name> \"Alice\"",
+        ))
+        .stdout(contains("→ 15 duplicate triples"))
+        .stdout(contains("→ 0 duplicate triples"))
+        .stdout(contains("Store now contains 18 triples"));
+}
+
+#[test]
+fn count_duplicates_only_prints_counts() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["load", "--count-duplicates", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .assert()
+        .success()
+        .stderr(contains("Duplicate triple").not())
+        .stdout(contains("→ 15 duplicate triples"));
+}
+
+#[test]
+fn count_duplicates_keeps_blank_nodes_of_files_apart() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    let a = store.dir.path().join("a.nt");
+    let b = store.dir.path().join("b.nt");
+    std::fs::write(&a, "_:x <http://e/p> \"1\" .\n_:x <http://e/p> \"1\" .\n").unwrap();
+    std::fs::write(&b, "_:x <http://e/q> \"2\" .\n").unwrap();
+
+    for _ in 0..2 {
+        typox(&store.dir)
+            .args(["load", "--count-duplicates", "-s"])
+            .arg(store.path())
+            .arg("-f")
+            .arg(&a)
+            .arg(&b)
+            .assert()
+            .success()
+            .stdout(contains("→ 1 duplicate triples"))
+            .stdout(contains("→ 0 duplicate triples"));
+    }
+
+    // Each file of each run has its own _:x
+    let results = store.query("SELECT (COUNT(DISTINCT ?s) AS ?n) WHERE { ?s ?p ?o }");
+    assert_eq!(results, json!([{ "n": 4 }]));
+}
+
+#[test]
+fn graph_per_file_names_graphs_after_files() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    typox(&store.dir)
+        .args(["load", "--graph-per-file", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("people.nt"))
+        .assert()
+        .success();
+
+    let graph =
+        url::Url::from_file_path(std::fs::canonicalize(fixture("people.nt")).unwrap()).unwrap();
+    let results = store.query(&format!(
+        "SELECT (COUNT(*) AS ?n) WHERE {{ GRAPH <{}> {{ ?s ?p ?o }} }}",
+        graph
+    ));
+    assert_eq!(results, serde_json::json!([{ "n": 3 }]));
+}
+
+#[test]
+fn graph_mapping_assigns_listed_files() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    let mapping = store.dir.path().join("graphs.json");
+    std::fs::write(
+        &mapping,
+        serde_json::json!({ fixture("people.nt").to_str().unwrap(): "http://example.org/graphs/nt" })
+            .to_string(),
+    )
+    .unwrap();
+    typox(&store.dir)
+        .args(["load", "--graph-mapping"])
+        .arg(&mapping)
+        .arg("-s")
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stdout(contains("→ Graph: <http://example.org/graphs/nt>").count(1));
+
+    let results =
+        store.query("SELECT ?g (COUNT(*) AS ?n) WHERE { GRAPH ?g { ?s ?p ?o } } GROUP BY ?g");
+    assert_eq!(
+        results,
+        serde_json::json!([{ "g": "ex:graphs/nt", "n": 3 }])
+    );
+}
+
+#[test]
+fn report_overlap_lists_new_and_shared_triples() {
+    let store = TestStore::load(&["people.nt"]);
+    typox(&store.dir)
+        .args(["load", "--report-overlap", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("extra/books.ttl"))
+        .arg(fixture("people.ttl"))
+        .assert()
+        .success()
+        .stdout(contains(
+            "people.ttl: 15 triples, 15 new, 0 already present",
+        ))
+        .stdout(contains(
+            "people.ttl: 15 triples, 0 new, 15 already present",
+        ))
+        .stdout(contains("people.ttl: 15 shared triples"))
+        .stdout(contains("books.ttl ∩").not())
+        .stdout(contains("Store now contains 20 triples"));
+}
+
+#[test]
+fn generate_void_describes_loaded_data() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    for _ in 0..2 {
+        typox(&store.dir)
+            .args(["load", "--generate-void", "-s"])
+            .arg(store.path())
+            .arg("-f")
+            .arg(fixture("people.ttl"))
+            .assert()
+            .success()
+            .stdout(contains("with 1 classes written to graph"));
+    }
+
+    let results = store.query(
+        "PREFIX void: <http://rdfs.org/ns/void#>
+         SELECT ?triples ?subjects ?class ?entities WHERE {
+           GRAPH void:dataset {
+             ?d void:triples ?triples ; void:distinctSubjects ?subjects ;
+                void:classPartition [ void:class ?class ; void:entities ?entities ]
+           }
+         }",
+    );
+    assert_eq!(
+        results,
+        serde_json::json!([{ "triples": 15, "subjects": 3, "class": "foaf:Person", "entities": 3 }])
+    );
+}
+
+#[test]
+fn no_create_refuses_missing_store() {
+    let dir = TempDir::new().unwrap();
+    let store_path = dir.path().join("store");
+    typox(&dir)
+        .args(["load", "--no-create", "-s"])
+        .arg(&store_path)
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .assert()
+        .failure()
+        .stderr(contains("Store path does not exist"));
+    assert!(!store_path.exists());
+
+    let store = TestStore::load(&["people.nt"]);
+    typox(&store.dir)
+        .args(["load", "--no-create", "-s"])
+        .arg(store.path())
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .assert()
+        .success()
+        .stdout(contains("Store now contains 18 triples"));
+}
+
+#[test]
+fn sparql_update_files_are_executed() {
+    let dir = TempDir::new().unwrap();
+    let script = dir.path().join("fix.ru");
+    std::fs::write(
+        &script,
+        "INSERT DATA { <http://example.org/alice> <http://example.org/reviewed> true }",
+    )
+    .unwrap();
+
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("Executing SPARQL UPDATE script"))
+        .stdout(contains("Store went from 15 to 16 triples"));
+}
+
+#[test]
+fn sparql_update_files_matched_by_globs_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let data = dir.path().join("data");
+    std::fs::create_dir(&data).unwrap();
+    std::fs::copy(fixture("people.ttl"), data.join("people.ttl")).unwrap();
+    std::fs::write(data.join("wipe.ru"), "DROP ALL").unwrap();
+
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(data.join("*"))
+        .assert()
+        .success()
+        .stderr(contains("Skipping SPARQL UPDATE script"))
+        .stdout(contains("Executing SPARQL UPDATE script").not())
+        .stdout(contains("Store now contains 15 triples"));
+}
+
+#[test]
+fn deduplicate_files_skips_identical_content() {
+    let dir = TempDir::new().unwrap();
+    let data = dir.path().join("data");
+    std::fs::create_dir(&data).unwrap();
+    for name in ["a.ttl", "b.ttl"] {
+        std::fs::copy(fixture("people.ttl"), data.join(name)).unwrap();
+    }
+
+    typox(&dir)
+        .args(["load", "--deduplicate-files", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(data.join("*.ttl"))
+        .assert()
+        .success()
+        .stdout(contains("Skipping file"))
+        .stdout(contains("b.ttl (same content as"))
+        .stdout(contains("Successfully loaded 15 total triples"));
+
+    // Each copy goes to its own graph, so neither is a duplicate
+    typox(&dir)
+        .args(["load", "--deduplicate-files", "--graph-per-file", "-s"])
+        .arg(dir.path().join("graphs"))
+        .arg("-f")
+        .arg(data.join("*.ttl"))
+        .assert()
+        .success()
+        .stdout(contains("Skipping file").not())
+        .stdout(contains("Successfully loaded 30 total triples"));
+}
+
+#[cfg(unix)]
+#[test]
+fn named_pipes_are_streamed() {
+    let dir = TempDir::new().unwrap();
+    let pipe = dir.path().join("people.ttl");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&pipe)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Opening a pipe for writing blocks until typox opens it for reading. If typox
+    // never does, the writer is left blocked and the test fails instead of hanging.
+    let (written, writer_done) = std::sync::mpsc::channel();
+    {
+        let pipe = pipe.clone();
+        std::thread::spawn(move || {
+            std::fs::write(pipe, std::fs::read(fixture("people.ttl")).unwrap()).unwrap();
+            written.send(()).unwrap();
+        });
+    }
+    typox(&dir)
+        .args(["load", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&pipe)
+        .timeout(std::time::Duration::from_secs(60))
+        .assert()
+        .success()
+        .stdout(contains("Loaded 15 triples"));
+    writer_done
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("typox did not read the named pipe");
+}
+
+#[cfg(unix)]
+#[test]
+fn options_reading_files_twice_reject_named_pipes() {
+    let dir = TempDir::new().unwrap();
+    let pipe = dir.path().join("people.ttl");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&pipe)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    typox(&dir)
+        .args(["load", "--verify", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&pipe)
+        .timeout(std::time::Duration::from_secs(60))
+        .assert()
+        .failure()
+        .stderr(contains(
+            "--verify reads the files twice and cannot be used with",
+        ));
+}
+
+#[test]
+fn progress_reports_every_ten_thousand_triples() {
+    let dir = TempDir::new().unwrap();
+    let data = dir.path().join("numbers.nt");
+    let triples: String = (0..25_000)
+        .map(|n| format!("<http://example.org/n{n}> <http://example.org/value> \"{n}\" .\n"))
+        .collect();
+    std::fs::write(&data, triples).unwrap();
+    typox(&dir)
+        .args(["load", "--progress", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(&data)
+        .assert()
+        .success()
+        .stdout(contains("… 10000 triples, "))
+        .stdout(contains("… 20000 triples, "))
+        .stdout(contains("bytes remaining").count(2))
+        .stdout(contains("Loaded 25000 triples"));
+}
+
+#[test]
+fn verify_checks_loaded_triples() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["load", "--verify", "--graph-per-file", "-s"])
+        .arg(dir.path().join("store"))
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("people.nt"))
+        .assert()
+        .success()
+        .stdout(contains("Verified 18 sampled triples"));
+}
+
+#[test]
+fn validate_iris_reports_every_invalid_iri() {
+    let dir = TempDir::new().unwrap();
+    let store_path = dir.path().join("store");
+    typox(&dir)
+        .args(["load", "--validate-iris", "-s"])
+        .arg(&store_path)
+        .arg("-f")
+        .arg(fixture("people.ttl"))
+        .arg(fixture("invalid-iris.ttl"))
+        .assert()
+        .failure()
+        .stderr(contains(
+            "invalid-iris.ttl: invalid IRI <http://example.org/alice home>",
+        ))
+        .stderr(contains("invalid-iris.ttl: invalid IRI <alice>"))
+        .stderr(contains("2 invalid IRIs found, nothing was loaded"));
+    assert!(!store_path.exists());
+}
+
+#[test]
+fn manifest_entries_set_format_and_graph() {
+    let store = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    std::fs::copy(fixture("people.rdf"), store.dir.path().join("people.xml")).unwrap();
+    let manifest = store.dir.path().join("manifest.json");
+    std::fs::write(
+        &manifest,
+        serde_json::json!([
+            { "file": fixture("people.ttl"), "graph": "http://example.org/graphs/ttl" },
+            { "file": "people.xml", "format": "rdfxml" },
+            // The same file listed again keeps the graph of each entry
+            { "file": fixture("people.ttl"), "graph": "http://example.org/graphs/copy" },
+        ])
+        .to_string(),
+    )
+    .unwrap();
+    typox(&store.dir)
+        .args(["load", "--manifest"])
+        .arg(&manifest)
+        .arg("-s")
+        .arg(store.path())
+        .assert()
+        .success()
+        .stdout(contains("→ Graph: <http://example.org/graphs/ttl>").count(1))
+        .stdout(contains("→ Graph: <http://example.org/graphs/copy>").count(1));
+
+    let results = store.query(
+        "SELECT ?g (COUNT(*) AS ?n) WHERE { GRAPH ?g { ?s ?p ?o } } GROUP BY ?g ORDER BY ?g",
+    );
+    assert_eq!(
+        results,
+        serde_json::json!([{ "g": "ex:graphs/copy", "n": 15 }, { "g": "ex:graphs/ttl", "n": 15 }])
+    );
+}
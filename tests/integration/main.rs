@@ -0,0 +1,98 @@
+// Copyright (c) 2024 Typox Project Contributors
+// Licensed under the MIT License - see LICENSE file for details
+
+//! End-to-end tests that run the `typox` binary against the RDF fixtures in
+//! `tests/integration/fixtures`.
+
+mod batch;
+mod errors;
+mod http;
+mod info;
+mod load;
+mod merge;
+mod query;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_cmd::Command;
+use oxigraph::io::RdfFormat;
+use oxigraph::store::Store;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+// Declared in front of every query run through TestStore::query
+pub const PREFIXES: &str =
+    "PREFIX ex: <http://example.org/>\nPREFIX foaf: <http://xmlns.com/foaf/0.1/>\n";
+
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/integration/fixtures")
+        .join(name)
+}
+
+// Every invocation gets its own cache and configuration directories so that HTTP
+// responses cached by one test never leak into another, and ignores the caller's
+// TYPOX_* variables and config.toml
+pub fn typox(cache_dir: &TempDir) -> Command {
+    let mut cmd = cargo_bin_cmd!("typox");
+    cmd.env("XDG_CACHE_HOME", cache_dir.path())
+        .env("XDG_CONFIG_HOME", cache_dir.path())
+        .env_remove("TYPOX_STORE")
+        .env_remove("TYPOX_ENDPOINT_BEARER_TOKEN");
+    cmd
+}
+
+pub struct TestStore {
+    pub dir: TempDir,
+}
+
+impl TestStore {
+    pub fn path(&self) -> PathBuf {
+        self.dir.path().join("store")
+    }
+
+    // Load the given fixtures through `typox load`
+    pub fn load(fixtures: &[&str]) -> Self {
+        let store = TestStore {
+            dir: TempDir::new().unwrap(),
+        };
+        let mut cmd = typox(&store.dir);
+        cmd.arg("load").arg("-s").arg(store.path()).arg("-f");
+        for name in fixtures {
+            cmd.arg(fixture(name));
+        }
+        cmd.assert().success();
+        store
+    }
+
+    // Build a store directly with oxigraph, for formats `typox load` can't read
+    pub fn from_fixture(name: &str, format: RdfFormat) -> Self {
+        let store = TestStore {
+            dir: TempDir::new().unwrap(),
+        };
+        let oxigraph_store = Store::open(store.path()).unwrap();
+        let data = std::fs::read(fixture(name)).unwrap();
+        oxigraph_store
+            .load_from_reader(format, data.as_slice())
+            .unwrap();
+        oxigraph_store.flush().unwrap();
+        store
+    }
+
+    pub fn query(&self, sparql: &str) -> Value {
+        let output = typox(&self.dir)
+            .arg("query")
+            .arg("-s")
+            .arg(self.path())
+            .arg("-q")
+            .arg(format!("{}{}", PREFIXES, sparql))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "query failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
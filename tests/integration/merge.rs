@@ -0,0 +1,33 @@
+use crate::{typox, TestStore};
+use oxigraph::io::RdfFormat;
+use predicates::str::contains;
+use tempfile::TempDir;
+
+#[test]
+fn merge_copies_quads_and_skips_duplicates() {
+    let people = TestStore::load(&["people.ttl", "people.nt"]);
+    let graphs = TestStore::from_fixture("graphs.nq", RdfFormat::NQuads);
+    let merged = TestStore {
+        dir: TempDir::new().unwrap(),
+    };
+    typox(&merged.dir)
+        .args(["merge", "-o"])
+        .arg(merged.path())
+        .arg("-i")
+        .arg(people.path())
+        .arg("-i")
+        .arg(graphs.path())
+        .arg("-i")
+        .arg(people.path())
+        .assert()
+        .success()
+        .stdout(contains(
+            "Merged 41 quads from 3 stores (19 duplicates skipped)",
+        ))
+        .stdout(contains("Store now contains 22 quads"));
+
+    let results = merged.query(
+        "SELECT (COUNT(*) AS ?n) WHERE { GRAPH <http://example.org/graphs/hr> { ?s ?p ?o } }",
+    );
+    assert_eq!(results, serde_json::json!([{ "n": 3 }]));
+}
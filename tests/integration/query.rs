@@ -0,0 +1,798 @@
+use crate::{typox, TestStore, PREFIXES};
+use oxigraph::io::RdfFormat;
+use predicates::prelude::*;
+use predicates::str::contains;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn select_returns_one_object_per_row() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("SELECT ?name WHERE { ?p foaf:name ?name } ORDER BY ?name");
+    assert_eq!(
+        results,
+        json!([{ "name": "Alice" }, { "name": "Bob" }, { "name": "Carol" }])
+    );
+}
+
+#[test]
+fn integer_literals_become_numbers() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("SELECT ?age WHERE { ?p foaf:age ?age } ORDER BY ?age");
+    assert_eq!(
+        results,
+        json!([{ "age": 27 }, { "age": 34 }, { "age": 41 }])
+    );
+}
+
+#[test]
+fn decimal_and_double_literals_become_numbers() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results =
+        store.query("SELECT ?height ?score WHERE { ex:alice ex:height ?height ; ex:score ?score }");
+    assert_eq!(results, json!([{ "height": 1.68, "score": 9.5 }]));
+}
+
+#[test]
+fn other_literals_keep_their_lexical_form() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("SELECT ?name ?born ?employed WHERE { ex:alice foaf:name ?name ; ex:born ?born ; ex:employed ?employed }");
+    assert_eq!(
+        results,
+        json!([{ "name": "Alice", "born": "1990-04-12", "employed": true }])
+    );
+}
+
+#[test]
+fn iris_are_shortened_with_query_prefixes() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("SELECT ?person ?type WHERE { ?person a ?type ; foaf:age 34 }");
+    assert_eq!(
+        results,
+        json!([{ "person": "ex:alice", "type": "foaf:Person" }])
+    );
+}
+
+#[test]
+fn empty_result_set_is_an_error() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?s WHERE {{ ?s foaf:name \"Nobody\" }}",
+            PREFIXES
+        ))
+        .assert()
+        .failure()
+        .stderr(contains("No records found for the given query"));
+}
+
+#[test]
+fn ask_query_returns_boolean_result() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("ASK { ex:alice foaf:knows ex:bob }");
+    assert_eq!(results, json!({ "result": true }));
+
+    let results = store.query("ASK { ex:bob foaf:knows ex:alice }");
+    assert_eq!(results, json!({ "result": false }));
+}
+
+#[test]
+fn construct_query_returns_turtle_graph() {
+    let store = TestStore::load(&["people.ttl"]);
+    let results = store.query("CONSTRUCT { ?a ex:friendOf ?b } WHERE { ?a foaf:knows ?b }");
+    assert_eq!(results["type"], "graph");
+    let turtle = results["turtle"].as_str().unwrap();
+    assert!(turtle.contains("<http://example.org/friendOf>"));
+    assert_eq!(turtle.matches("friendOf").count(), 2);
+}
+
+#[test]
+fn named_graph_query() {
+    let store = TestStore::from_fixture("graphs.nq", RdfFormat::NQuads);
+    let results = store.query("SELECT ?name ?salary WHERE { GRAPH <http://example.org/graphs/hr> { ?p foaf:name ?name ; ex:salary ?salary } }");
+    assert_eq!(results, json!([{ "name": "Alice", "salary": 5200 }]));
+
+    let results = store.query(
+        "SELECT ?g (COUNT(*) AS ?n) WHERE { GRAPH ?g { ?s ?p ?o } } GROUP BY ?g ORDER BY ?g",
+    );
+    assert_eq!(
+        results,
+        json!([
+            { "g": "ex:graphs/hr", "n": 3 },
+            { "g": "ex:graphs/projects", "n": 1 }
+        ])
+    );
+}
+
+#[test]
+fn rdf_xml_data_is_queryable() {
+    let store = TestStore::from_fixture("people.rdf", RdfFormat::RdfXml);
+    let results = store.query("SELECT ?name ?age WHERE { ?p foaf:name ?name ; foaf:age ?age }");
+    assert_eq!(results, json!([{ "name": "Erin", "age": 29 }]));
+}
+
+#[test]
+fn legacy_mode_matches_query_subcommand() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .arg("-s")
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name WHERE {{ ?p foaf:name ?name }} ORDER BY ?name",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        results,
+        store.query("SELECT ?name WHERE { ?p foaf:name ?name } ORDER BY ?name")
+    );
+}
+
+#[test]
+fn ndjson_format_writes_one_row_per_line() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .args(["query", "--format", "ndjson", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name ?age WHERE {{ ?p foaf:name ?name ; foaf:age ?age }} ORDER BY ?age",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"age\":27,\"name\":\"Bob\"}\n{\"age\":34,\"name\":\"Alice\"}\n{\"age\":41,\"name\":\"Carol\"}\n"
+    );
+}
+
+#[test]
+fn store_defaults_to_typox_store_variable() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .env("TYPOX_STORE", store.path())
+        .args(["query", "-q", "SELECT (COUNT(*) AS ?n) WHERE { ?s ?p ?o }"])
+        .assert()
+        .success()
+        .stdout(contains("\"n\": 15"));
+}
+
+#[test]
+fn config_file_supplies_defaults() {
+    let store = TestStore::load(&["people.ttl"]);
+    std::fs::create_dir(store.dir.path().join("typox")).unwrap();
+    std::fs::write(
+        store.dir.path().join("typox/config.toml"),
+        format!(
+            "default_store = {:?}\ndefault_format = \"ndjson\"\n\n[prefixes]\npeople = \"http://example.org/\"\n",
+            store.path()
+        ),
+    )
+    .unwrap();
+    let query = "SELECT ?p WHERE { ?p <http://xmlns.com/foaf/0.1/age> 34 }";
+
+    typox(&store.dir)
+        .args(["query", "-q", query])
+        .assert()
+        .success()
+        .stdout("{\"p\":\"people:alice\"}\n");
+    typox(&store.dir)
+        .args(["query", "--format", "json", "-q", query])
+        .assert()
+        .success()
+        .stdout(contains("\"p\": \"people:alice\""));
+}
+
+#[test]
+fn output_file_receives_results() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output_path = store.dir.path().join("results.json");
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT (COUNT(*) AS ?n) WHERE {{ ?s a foaf:Person }}",
+            PREFIXES
+        ))
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stdout(contains("Results written to"));
+
+    let written: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&output_path).unwrap()).unwrap();
+    assert_eq!(written, json!([{ "n": 3 }]));
+}
+
+#[test]
+fn failing_query_keeps_previous_output_file() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output_path = store.dir.path().join("results.json");
+    std::fs::write(&output_path, "[{\"n\": 3}]").unwrap();
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .args(["-q", "SELECT WHERE {", "-o"])
+        .arg(&output_path)
+        .assert()
+        .failure();
+
+    assert_eq!(
+        std::fs::read_to_string(&output_path).unwrap(),
+        "[{\"n\": 3}]"
+    );
+}
+
+#[test]
+fn var_binds_strings_and_iris() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?age WHERE {{ ?person foaf:name ?name ; foaf:age ?age }}",
+            PREFIXES
+        ))
+        .args(["--var", "name=Bob"])
+        .assert()
+        .success()
+        .stdout(contains("\"age\": 27"));
+
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name WHERE {{ ?person foaf:name ?name }}",
+            PREFIXES
+        ))
+        .args(["--var", "person=<http://example.org/carol>"])
+        .assert()
+        .success()
+        .stdout(contains("\"name\": \"Carol\""));
+}
+
+#[test]
+fn var_values_cannot_inject_sparql() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?person WHERE {{ ?person foaf:name ?name }}",
+            PREFIXES
+        ))
+        .args(["--var", "name=Bob\") } UNION { ?person ?p (\""])
+        .assert()
+        .failure()
+        .stderr(contains("No records found for the given query"));
+}
+
+#[test]
+fn timeout_exits_with_code_124() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output_path = store.dir.path().join("results.json");
+    // A six-way cross product of the store, far longer than a second to count. The
+    // FILTER reads the store for every row, which is where cancellation is noticed.
+    typox(&store.dir)
+        .args(["query", "--timeout", "1", "-s"])
+        .arg(store.path())
+        .args(["-q", "SELECT (COUNT(*) AS ?n) WHERE { ?a ?b ?c . ?d ?e ?f . ?g ?h ?i . ?j ?k ?l . ?m ?n1 ?o . ?p ?q ?r FILTER EXISTS { ?a ?b ?c } }"])
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .code(124)
+        .stderr(contains("Query timed out after 1 seconds"));
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn table_format_aligns_columns_in_select_order() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .args(["query", "--format", "table", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name ?age WHERE {{ ?p foaf:name ?name ; foaf:age ?age }} ORDER BY ?age",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "+-------+-----+\n\
+         | name  | age |\n\
+         +-------+-----+\n\
+         | Bob   | 27  |\n\
+         | Alice | 34  |\n\
+         | Carol | 41  |\n\
+         +-------+-----+\n"
+    );
+}
+
+#[test]
+fn color_only_applies_to_table_output() {
+    let store = TestStore::load(&["people.ttl"]);
+    let query = format!(
+        "{}SELECT ?p ?age WHERE {{ ?p foaf:age 27 ; foaf:age ?age }}",
+        PREFIXES
+    );
+
+    typox(&store.dir)
+        .args(["query", "--format", "table", "--color", "-s"])
+        .arg(store.path())
+        .args(["-q", &query])
+        .assert()
+        .success()
+        .stdout(contains("\u{1b}[34mex:bob\u{1b}[0m"))
+        .stdout(contains("\u{1b}[36m27\u{1b}[0m"));
+
+    typox(&store.dir)
+        .args(["query", "--color", "-s"])
+        .arg(store.path())
+        .args(["-q", &query])
+        .assert()
+        .success()
+        .stdout(contains("\u{1b}").not());
+}
+
+#[test]
+fn numeric_looking_strings_stay_strings() {
+    let dir = TempDir::new().unwrap();
+    let assert = typox(&dir)
+        .current_dir(dir.path())
+        .args(["query", "-s", "memory://scratch", "-q"])
+        .arg(r#"SELECT ?code ?tagged ?flag WHERE { BIND("00123" AS ?code) BIND("007"@en AS ?tagged) BIND("true"^^<http://www.w3.org/2001/XMLSchema#boolean> AS ?flag) }"#)
+        .assert()
+        .success();
+    let results: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        results,
+        json!([{ "code": "00123", "tagged": "007", "flag": true }])
+    );
+}
+
+#[test]
+fn memory_store_starts_empty() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .current_dir(dir.path())
+        .args(["query", "-s", "memory://scratch", "-q"])
+        .arg("SELECT ?sum (EXISTS { ?s ?p ?o } AS ?data) WHERE { BIND(1 + 2 AS ?sum) }")
+        .assert()
+        .success()
+        .stdout(contains("\"sum\": 3"))
+        .stdout(contains("\"data\": false"));
+}
+
+#[test]
+fn explain_prints_plan_instead_of_results() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .args(["query", "--explain", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name WHERE {{ ?p foaf:name ?name ; foaf:age ?age }}",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let explanation: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(explanation["plan"]["name"], "Project(?name)");
+    assert_eq!(explanation["plan"]["number of results"], 3);
+    assert!(explanation.to_string().contains("QuadPattern"));
+}
+
+#[test]
+fn unsigned_long_beyond_i64_stays_a_string() {
+    let dir = TempDir::new().unwrap();
+    typox(&dir)
+        .args(["query", "-s", "memory://scratch", "-q"])
+        .arg(
+            "PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>\n\
+             SELECT ?max ?fits WHERE { \
+             BIND(\"18446744073709551615\"^^xsd:unsignedLong AS ?max) \
+             BIND(\"9223372036854775807\"^^xsd:unsignedLong AS ?fits) }",
+        )
+        .assert()
+        .success()
+        .stdout(contains("\"max\": \"18446744073709551615\""))
+        .stdout(contains("\"fits\": 9223372036854775807"))
+        .stderr(contains(
+            "18446744073709551615 does not fit in a 64-bit integer",
+        ));
+}
+
+// Runs a query against an empty in-memory store, as prefix handling needs no data
+fn query_without_store(query: &str) -> serde_json::Value {
+    let dir = TempDir::new().unwrap();
+    let output = typox(&dir)
+        .args(["query", "-s", "memory://scratch", "-q", query])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn prefixes_in_comments_are_ignored() {
+    let results = query_without_store(
+        "# PREFIX wrong: <http://example.org/>\n\
+         \n\
+         PREFIX ex: <http://example.org/>\n\
+         \n\
+         # PREFIX other: <http://other.example/>\n\
+         \n\
+         PREFIX a: <http://a.example/>\n\
+         SELECT ?x ?y WHERE { BIND(ex:alice AS ?x) BIND(<http://other.example/1> AS ?y) }",
+    );
+    assert_eq!(
+        results,
+        json!([{ "x": "ex:alice", "y": "http://other.example/1" }])
+    );
+}
+
+#[test]
+fn prefixes_can_span_lines_and_share_a_line() {
+    let results = query_without_store(
+        "prefix\tex:\n    <http://example.org/>\n\
+         PREFIX a: <http://a.example/> PREFIX b:<http://b.example/>\n\
+         SELECT ?x ?y ?z WHERE { BIND(ex:alice AS ?x) BIND(a:1 AS ?y) BIND(b:2 AS ?z) }",
+    );
+    assert_eq!(
+        results,
+        json!([{ "x": "ex:alice", "y": "a:1", "z": "b:2" }])
+    );
+}
+
+#[test]
+fn relative_prefixes_resolve_against_base() {
+    let results = query_without_store(
+        "BASE <http://example.org/>\n\
+         PREFIX people: <people/>\n\
+         SELECT ?x WHERE { BIND(people:alice AS ?x) }",
+    );
+    assert_eq!(results, json!([{ "x": "people:alice" }]));
+}
+
+#[test]
+fn overlapping_prefixes_use_the_longest_namespace() {
+    let results = query_without_store(
+        "PREFIX a: <http://example.org/>\n\
+         PREFIX b: <http://example.org/people/>\n\
+         PREFIX c: <http://example.org/people/staff/>\n\
+         PREFIX d: <http://example.org/peo>\n\
+         SELECT ?x ?y WHERE { BIND(c:alice AS ?x) BIND(b:bob AS ?y) }",
+    );
+    assert_eq!(results, json!([{ "x": "c:alice", "y": "b:bob" }]));
+}
+
+#[test]
+fn include_schema_lists_variables_in_select_order() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .args(["query", "--include-schema", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name ?missing WHERE {{ ex:alice foaf:name ?name OPTIONAL {{ ex:alice ex:nothing ?missing }} }}",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        results,
+        json!({ "schema": ["name", "missing"], "data": [{ "name": "Alice" }] })
+    );
+}
+
+#[test]
+fn sparql_json_format_follows_the_w3c_results_format() {
+    let store = TestStore::load(&["people.ttl"]);
+    let output = typox(&store.dir)
+        .args(["query", "--format", "sparql-json", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?person ?name WHERE {{ ?person foaf:name ?name FILTER(?person = ex:alice) }}",
+            PREFIXES
+        ))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        results,
+        json!({
+            "head": { "vars": ["person", "name"] },
+            "results": { "bindings": [{
+                "person": { "type": "uri", "value": "http://example.org/alice" },
+                "name": { "type": "literal", "value": "Alice", "xml:lang": "en" }
+            }] }
+        })
+    );
+
+    typox(&store.dir)
+        .args(["query", "--format", "sparql-json", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!("{}ASK {{ ex:alice foaf:name ?name }}", PREFIXES))
+        .assert()
+        .success()
+        .stdout(contains(r#""boolean":true"#));
+}
+
+#[test]
+fn dot_format_turns_rows_and_triples_into_edges() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "--format", "dot", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?source ?label ?target WHERE {{ ?source foaf:knows ?target BIND(\"knows\" AS ?label) }}",
+            PREFIXES
+        ))
+        .assert()
+        .success()
+        .stdout(contains("digraph {\n"))
+        .stdout(contains(r#""<http://example.org/alice>" [label="alice"];"#))
+        .stdout(contains(
+            r#""<http://example.org/alice>" -> "<http://example.org/bob>" [label="knows"];"#,
+        ));
+
+    typox(&store.dir)
+        .args(["query", "--format", "dot", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}CONSTRUCT {{ ex:bob foaf:name ?name }} WHERE {{ ex:bob foaf:name ?name }}",
+            PREFIXES
+        ))
+        .assert()
+        .success()
+        .stdout(contains(
+            r#""<http://example.org/bob>" -> "\"Bob\"" [label="name"];"#,
+        ));
+
+    typox(&store.dir)
+        .args(["query", "--format", "dot", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?person WHERE {{ ?person a foaf:Person }}",
+            PREFIXES
+        ))
+        .assert()
+        .failure()
+        .stderr(contains("?source, ?label and ?target"));
+}
+
+#[test]
+fn query_file_runs_script_statements_in_order() {
+    let store = TestStore::load(&["people.ttl"]);
+    let script = store.dir.path().join("script.rq");
+    std::fs::write(
+        &script,
+        format!(
+            "{p}INSERT DATA {{ ex:dave foaf:age 52 }}\n;;;\n\
+             {p}SELECT ?age WHERE {{ ex:dave foaf:age ?age }}\n---\n\
+             {p}DELETE WHERE {{ ex:dave foaf:age ?age }}\n---\n\
+             {p}ASK {{ ex:dave foaf:age ?age }}\n",
+            p = PREFIXES
+        ),
+    )
+    .unwrap();
+
+    let output = typox(&store.dir)
+        .args(["query", "-s"])
+        .arg(store.path())
+        .arg("--query-file")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results, json!([{ "age": 52 }, { "result": false }]));
+}
+
+#[test]
+fn scripts_reject_formats_of_single_results() {
+    let store = TestStore::load(&["people.ttl"]);
+    let script = store.dir.path().join("script.rq");
+    std::fs::write(
+        &script,
+        format!(
+            "{p}ASK {{ ?s ?p ?o }}\n---\n{p}ASK {{ ex:dave ?p ?o }}\n",
+            p = PREFIXES
+        ),
+    )
+    .unwrap();
+
+    for format in ["sparql-json", "dot"] {
+        typox(&store.dir)
+            .args(["query", "--format", format, "-s"])
+            .arg(store.path())
+            .arg("--query-file")
+            .arg(&script)
+            .assert()
+            .failure()
+            .stderr(contains(format!(
+                "--format {} cannot be used with a SPARQL script",
+                format
+            )));
+    }
+}
+
+#[test]
+fn separator_lines_in_long_literals_do_not_split_queries() {
+    let store = TestStore::load(&["people.ttl"]);
+    let query = "SELECT ?text WHERE { BIND(\"\"\"before\n---\nafter\"\"\" AS ?text) }";
+    let query_file = store.dir.path().join("query.rq");
+    std::fs::write(&query_file, query).unwrap();
+
+    for source in [
+        ["-q", query],
+        ["--query-file", query_file.to_str().unwrap()],
+    ] {
+        let output = typox(&store.dir)
+            .args(["query", "-s"])
+            .arg(store.path())
+            .args(source)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(results, json!([{ "text": "before\n---\nafter" }]));
+    }
+}
+
+#[test]
+fn latex_format_writes_escaped_tabular() {
+    let store = TestStore::load(&["people.ttl"]);
+    typox(&store.dir)
+        .args(["query", "--format", "latex", "-s"])
+        .arg(store.path())
+        .arg("-q")
+        .arg(format!(
+            "{}SELECT ?name ?age ?note_text WHERE {{ ex:bob foaf:name ?name ; foaf:age ?age BIND(\"50% & more_{{x}}\" AS ?note_text) }}",
+            PREFIXES
+        ))
+        .assert()
+        .success()
+        .stdout(concat!(
+            r"\begin{tabular}{lll}", "\n",
+            r"\hline", "\n",
+            r"name & age & note\_text \\", "\n",
+            r"\hline", "\n",
+            r"Bob & \num{27} & 50\% \& more\_\{x\} \\", "\n",
+            r"\hline", "\n",
+            r"\end{tabular}", "\n",
+        ));
+}
+
+#[test]
+fn query_template_substitutes_var_values() {
+    let store = TestStore::load(&["people.ttl"]);
+    let template = store.dir.path().join("by-name.rq");
+    std::fs::write(
+        &template,
+        format!(
+            "{}SELECT ?person WHERE {{ ?person foaf:name {{{{name}}}} }}",
+            PREFIXES
+        ),
+    )
+    .unwrap();
+
+    for (value, person) in [(r#"name="Alice"@en"#, "ex:alice"), ("name=Bob", "ex:bob")] {
+        typox(&store.dir)
+            .args(["query", "--query-template", "--var", value, "-s"])
+            .arg(store.path())
+            .arg("--query-file")
+            .arg(&template)
+            .assert()
+            .success()
+            .stdout(contains(format!("\"person\": \"{}\"", person)));
+    }
+
+    typox(&store.dir)
+        .args(["query", "--query-template", "-s"])
+        .arg(store.path())
+        .arg("--query-file")
+        .arg(&template)
+        .assert()
+        .failure()
+        .stderr(contains("Failed to render query template"));
+}
+
+#[test]
+fn query_template_escapes_nested_group_braces() {
+    let store = TestStore::load(&["people.ttl"]);
+    let template = store.dir.path().join("either.rq");
+    std::fs::write(
+        &template,
+        format!(
+            "{}SELECT ?person WHERE \\{{{{ ?person foaf:name {{{{name}}}} }} UNION {{ ?person foaf:nick {{{{name}}}} }}}}",
+            PREFIXES
+        ),
+    )
+    .unwrap();
+
+    typox(&store.dir)
+        .args(["query", "--query-template", "--var", "name=Bob", "-s"])
+        .arg(store.path())
+        .arg("--query-file")
+        .arg(&template)
+        .assert()
+        .success()
+        .stdout(contains("\"person\": \"ex:bob\""));
+}
+
+#[test]
+fn output_append_accumulates_results() {
+    let store = TestStore::load(&["people.ttl"]);
+    let json_path = store.dir.path().join("results.json");
+    let ndjson_path = store.dir.path().join("results.ndjson");
+    for person in ["ex:alice", "ex:bob"] {
+        let query = format!(
+            "{}SELECT ?age WHERE {{ {} foaf:age ?age }}",
+            PREFIXES, person
+        );
+        for (path, format) in [(&json_path, "json"), (&ndjson_path, "ndjson")] {
+            typox(&store.dir)
+                .args(["query", "--output-append", "--format", format, "-s"])
+                .arg(store.path())
+                .arg("-q")
+                .arg(&query)
+                .arg("-o")
+                .arg(path)
+                .assert()
+                .success();
+        }
+    }
+
+    let written: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+    assert_eq!(written, json!([{ "age": 34 }, { "age": 27 }]));
+    assert_eq!(
+        std::fs::read_to_string(&ndjson_path).unwrap(),
+        "{\"age\":34}\n{\"age\":27}\n"
+    );
+}
+
+#[test]
+fn output_append_rejects_single_document_formats() {
+    let store = TestStore::load(&["people.ttl"]);
+    for format in ["sparql-json", "dot", "latex"] {
+        typox(&store.dir)
+            .args(["query", "--output-append", "--format", format, "-s"])
+            .arg(store.path())
+            .args(["-q", "ASK { ?s ?p ?o }", "-o"])
+            .arg(store.dir.path().join("results"))
+            .assert()
+            .failure()
+            .stderr(contains(format!(
+                "--output-append cannot be used with --format {}",
+                format
+            )));
+    }
+}
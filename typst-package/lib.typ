@@ -70,6 +70,16 @@
   json(bytes(stores-json))
 }
 
+// Major version of the plugin's JSON contract this package is written for, see
+// the API compatibility table in the typox README
+#let supported-api-major = 1
+
+/// Get the version of the plugin's JSON contract, e.g. "1.0.0"
+#let oxapi-version() = str(typox.get_api_version())
+
+/// Whether the bundled plugin speaks an API version this package understands
+#let oxapi-compatible() = int(oxapi-version().split(".").first()) == supported-api-major
+
 /// Get the size of a store (number of triples)
 #let oxstore-size(store-name) = {
   let result = str(typox.get_store_size(bytes(store-name)))